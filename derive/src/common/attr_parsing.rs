@@ -11,6 +11,30 @@ pub struct OCamlAttributes {
     pub as_: Option<String>,
     pub tag: Option<String>,
     pub polymorphic_variant: bool,
+    pub float_record: bool,
+    pub unboxed: bool,
+    pub tuple: bool,
+    pub definition: bool,
+    pub inline_record: bool,
+    pub boxed_record: bool,
+    pub mutable: bool,
+    pub skip: bool,
+    pub phantom: bool,
+    pub default: Option<FieldDefault>,
+    pub with: Option<String>,
+    pub rename: Option<String>,
+    pub and: Option<String>,
+    pub extensible: Option<String>,
+}
+
+/// Value used for a skipped field when converting from OCaml
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDefault {
+    /// `#[ocaml(default)]`, uses `Default::default()`
+    Trait,
+
+    /// `#[ocaml(default = "path")]`, calls the function at `path`
+    Path(String),
 }
 
 impl OCamlAttributes {
@@ -49,6 +73,15 @@ impl OCamlAttributes {
             "name" => &mut ocaml_attrs.name,
             "as_" => &mut ocaml_attrs.as_,
             "tag" => &mut ocaml_attrs.tag,
+            "with" => &mut ocaml_attrs.with,
+            "rename" => &mut ocaml_attrs.rename,
+            "and" => &mut ocaml_attrs.and,
+            "extensible" => &mut ocaml_attrs.extensible,
+            "default" => {
+                let path = Self::parse_string_attribute(&name, value)?;
+                ocaml_attrs.default = Some(FieldDefault::Path(path));
+                return Ok(());
+            }
             _ => {
                 return Err(OCamlInteropError::attribute_error_spanned(
                     Some(name),
@@ -95,6 +128,36 @@ impl OCamlAttributes {
                             "polymorphic_variant" => {
                                 ocaml_attrs.polymorphic_variant = true;
                             }
+                            "float_record" => {
+                                ocaml_attrs.float_record = true;
+                            }
+                            "unboxed" => {
+                                ocaml_attrs.unboxed = true;
+                            }
+                            "tuple" => {
+                                ocaml_attrs.tuple = true;
+                            }
+                            "definition" => {
+                                ocaml_attrs.definition = true;
+                            }
+                            "inline_record" => {
+                                ocaml_attrs.inline_record = true;
+                            }
+                            "boxed_record" => {
+                                ocaml_attrs.boxed_record = true;
+                            }
+                            "mutable" => {
+                                ocaml_attrs.mutable = true;
+                            }
+                            "skip" => {
+                                ocaml_attrs.skip = true;
+                            }
+                            "phantom" => {
+                                ocaml_attrs.phantom = true;
+                            }
+                            "default" => {
+                                ocaml_attrs.default = Some(FieldDefault::Trait);
+                            }
                             _ => {
                                 return Err(OCamlInteropError::attribute_error_spanned(
                                     Some(name),
@@ -134,4 +197,46 @@ impl OCamlAttributes {
     pub fn is_polymorphic_variant(&self) -> bool {
         self.polymorphic_variant
     }
+    pub fn is_float_record(&self) -> bool {
+        self.float_record
+    }
+    pub fn is_unboxed(&self) -> bool {
+        self.unboxed
+    }
+    pub fn is_tuple(&self) -> bool {
+        self.tuple
+    }
+    pub fn is_definition(&self) -> bool {
+        self.definition
+    }
+    pub fn is_inline_record(&self) -> bool {
+        self.inline_record
+    }
+    pub fn is_boxed_record(&self) -> bool {
+        self.boxed_record
+    }
+    pub fn is_mutable(&self) -> bool {
+        self.mutable
+    }
+    pub fn is_skipped(&self) -> bool {
+        self.skip
+    }
+    pub fn is_phantom(&self) -> bool {
+        self.phantom
+    }
+    pub fn get_default(&self) -> &Option<FieldDefault> {
+        &self.default
+    }
+    pub fn get_with(&self) -> &Option<String> {
+        &self.with
+    }
+    pub fn get_rename(&self) -> &Option<String> {
+        &self.rename
+    }
+    pub fn get_and(&self) -> &Option<String> {
+        &self.and
+    }
+    pub fn get_extensible(&self) -> &Option<String> {
+        &self.extensible
+    }
 }
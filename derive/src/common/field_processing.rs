@@ -5,7 +5,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::Ident;
 
-use crate::common::parsing::FieldRep;
+use crate::common::parsing::{FieldRep, Recursion};
 
 pub fn make_field_var(idx: usize, span: Option<Span>) -> Ident {
     let span = span.unwrap_or_else(Span::call_site);
@@ -28,6 +28,88 @@ pub fn get_ocaml_type(field_rep: &FieldRep, _direction: TypeDirection) -> TokenS
     }
 }
 
+/// Panic message for the conversions of recursive fields, whose values are converted before
+/// the value that contains them
+const CONVERTED_CHILD_MESSAGE: &str = "recursive field should have been converted first";
+
+/// Expression that converts the Rust value in `field_access` to OCaml, using the `to_ocaml`
+/// function of the `#[ocaml(with = "module")]` module if present
+pub fn field_to_ocaml_expr(field_rep: &FieldRep, field_access: TokenStream) -> TokenStream {
+    // Recursive fields were already converted, and their values pushed to `converted`
+    match field_rep.recursion {
+        Some(Recursion::Boxed) => {
+            return quote! { converted.pop().expect(#CONVERTED_CHILD_MESSAGE).get(cr) };
+        }
+        Some(Recursion::OptionalBoxed) => {
+            return quote! {
+                #field_access.as_ref().map(|_| converted.pop().expect(#CONVERTED_CHILD_MESSAGE)).to_ocaml(cr)
+            };
+        }
+        Some(Recursion::List) => {
+            return quote! {
+                #field_access
+                    .iter()
+                    .map(|_| converted.pop().expect(#CONVERTED_CHILD_MESSAGE))
+                    .collect::<::std::vec::Vec<_>>()
+                    .to_ocaml(cr)
+            };
+        }
+        None => {}
+    }
+    match &field_rep.with_path {
+        Some(with) => quote! { #with::to_ocaml(&#field_access, cr) },
+        None => quote! { #field_access.to_ocaml(cr) },
+    }
+}
+
+/// Expression that converts the OCaml value in `ocaml_value` to Rust, using the `from_ocaml`
+/// function of the `#[ocaml(with = "module")]` module if present
+pub fn field_from_ocaml_expr(field_rep: &FieldRep, ocaml_value: TokenStream) -> TokenStream {
+    // Recursive fields were already converted, and their values pushed to `converted`
+    match field_rep.recursion {
+        Some(Recursion::Boxed) => {
+            return quote! {
+                ::std::boxed::Box::new(converted.pop().expect(#CONVERTED_CHILD_MESSAGE))
+            };
+        }
+        Some(Recursion::OptionalBoxed) => {
+            return quote! {
+                #ocaml_value.to_option().map(|_| ::std::boxed::Box::new(converted.pop().expect(#CONVERTED_CHILD_MESSAGE)))
+            };
+        }
+        Some(Recursion::List) => {
+            return quote! {
+                {
+                    let mut list = #ocaml_value;
+                    let mut children = ::std::vec::Vec::new();
+                    while let Some((_, tail)) = list.uncons() {
+                        children.push(converted.pop().expect(#CONVERTED_CHILD_MESSAGE));
+                        list = tail;
+                    }
+                    children
+                }
+            };
+        }
+        None => {}
+    }
+    match &field_rep.with_path {
+        Some(with) => quote! { #with::from_ocaml(#ocaml_value) },
+        None => quote! { #ocaml_value.to_rust() },
+    }
+}
+
+/// Expression for the value of a `#[ocaml(skip)]` or `#[ocaml(phantom)]` field when converting
+/// from OCaml
+pub fn field_default_expr(field_rep: &FieldRep) -> TokenStream {
+    if field_rep.attrs.is_phantom() {
+        return quote! { ::core::marker::PhantomData };
+    }
+    match &field_rep.default_path {
+        Some(path) => quote! { #path() },
+        None => quote! { ::core::default::Default::default() },
+    }
+}
+
 pub fn generate_field_conversion_and_storage(
     field_rep: &FieldRep,
     field_access: TokenStream,
@@ -37,9 +119,10 @@ pub fn generate_field_conversion_and_storage(
 ) -> TokenStream {
     let ocaml_type = get_ocaml_type(field_rep, TypeDirection::ToOCaml);
     let idx_lit = syn::LitInt::new(&format!("{field_idx}usize"), Span::call_site());
+    let conversion = field_to_ocaml_expr(field_rep, field_access);
 
     quote! {
-        let #var_name: ::ocaml_interop::OCaml<#ocaml_type> = #field_access.to_ocaml(cr);
+        let #var_name: ::ocaml_interop::OCaml<#ocaml_type> = #conversion;
         unsafe { ::ocaml_interop::internal::store_field(#container_expr, #idx_lit, #var_name.raw()); }
     }
 }
@@ -48,16 +131,28 @@ pub fn generate_struct_field_extractions(
     fields: &[FieldRep],
     ocaml_value_expr: TokenStream,
 ) -> Vec<TokenStream> {
+    let mut ocaml_idx = 0usize;
     fields
         .iter()
         .enumerate()
         .map(|(idx, field_rep)| {
             let var_name = Ident::new(&format!("rust_field_{idx}"), field_rep.span);
+            if field_rep.is_skipped() {
+                let default = field_default_expr(field_rep);
+                return quote! {
+                    let #var_name = #default;
+                };
+            }
             let ocaml_type = get_ocaml_type(field_rep, TypeDirection::FromOCaml);
-            let idx_lit = syn::LitInt::new(&format!("{idx}"), Span::call_site());
+            let idx_lit = syn::LitInt::new(&format!("{ocaml_idx}"), Span::call_site());
+            ocaml_idx += 1;
+            let conversion = field_from_ocaml_expr(
+                field_rep,
+                quote! { #ocaml_value_expr.field::<#ocaml_type>(#idx_lit) },
+            );
 
             quote! {
-                let #var_name = #ocaml_value_expr.field::<#ocaml_type>(#idx_lit).to_rust();
+                let #var_name = #conversion;
             }
         })
         .collect()
@@ -71,7 +166,9 @@ pub fn generate_struct_field_conversions(
     fields
         .iter()
         .enumerate()
-        .map(|(idx, field_rep)| {
+        .filter(|(_, field_rep)| !field_rep.is_skipped())
+        .enumerate()
+        .map(|(ocaml_idx, (idx, field_rep))| {
             let field_access = self_access_pattern(idx, field_rep);
             let var_name = if let Some(ident) = &field_rep.ident {
                 Ident::new(&format!("field_{ident}_ocaml"), field_rep.span)
@@ -84,7 +181,7 @@ pub fn generate_struct_field_conversions(
                 field_access,
                 &var_name,
                 container_expr.clone(),
-                idx,
+                ocaml_idx,
             )
         })
         .collect()
@@ -101,6 +198,18 @@ pub fn create_ocaml_block(size: usize, tag: usize) -> TokenStream {
     }
 }
 
+/// Expression that evaluates to the extension constructor registered under `name`, looked up
+/// once and cached in a `static`
+pub fn extension_constructor_expr(name: &str) -> TokenStream {
+    quote! {
+        {
+            static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor =
+                ::ocaml_interop::internal::ExtensionConstructor::new(#name);
+            CONSTRUCTOR.get()
+        }
+    }
+}
+
 pub fn create_ocaml_tuple(size: usize) -> TokenStream {
     let size_lit = syn::LitInt::new(&format!("{size}usize"), Span::call_site());
 
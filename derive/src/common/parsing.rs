@@ -10,8 +10,9 @@ use syn::{
 
 use crate::{
     common::{
-        attr_parsing::OCamlAttributes,
+        attr_parsing::{FieldDefault, OCamlAttributes},
         error::{OCamlInteropError, Result},
+        field_processing::{get_ocaml_type, TypeDirection},
     },
     export::core::{InteropTypeDetail, PrimitiveInteropType, ProcessedArg},
 };
@@ -37,6 +38,37 @@ pub enum EnumKind {
 
     /// A polymorphic variant, represented as hash values
     Polymorphic,
+
+    /// An extensible variant or an exception, whose constructors are identified by the
+    /// extension constructors registered with `Callback.register_exception`
+    Extensible,
+}
+
+/// How the fields of a struct variant are stored in OCaml
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// An inline record (`| Foo of { a : int; b : string }`), the fields are stored directly
+    /// in the constructor block. The default, and the only option for other variant kinds.
+    Inline,
+
+    /// A separate record type (`| Foo of foo`), the constructor block has a single field
+    /// pointing to the record block. Selected with `#[ocaml(boxed_record)]`.
+    Boxed,
+}
+
+/// How a field refers to the type that contains it. The conversions of these fields use an
+/// explicit stack instead of recursive calls, so that deep values don't overflow the native
+/// stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recursion {
+    /// A `Box<Self>` field
+    Boxed,
+
+    /// An `Option<Box<Self>>` field
+    OptionalBoxed,
+
+    /// A `Vec<Self>` field, represented by an OCaml list
+    List,
 }
 
 pub struct FieldRep {
@@ -44,6 +76,9 @@ pub struct FieldRep {
     pub ty: Type,
     pub attrs: OCamlAttributes,
     pub ocaml_as_type_override_ts: Option<TokenStream>,
+    pub with_path: Option<syn::Path>,
+    pub default_path: Option<syn::Path>,
+    pub recursion: Option<Recursion>,
     pub span: Span,
 }
 
@@ -52,6 +87,7 @@ pub struct VariantRep {
     pub fields: Vec<FieldRep>,
     pub attrs: OCamlAttributes,
     pub kind: VariantKind,
+    pub record_kind: RecordKind,
     pub span: Span,
 }
 
@@ -67,10 +103,141 @@ pub enum TypeRepData {
 
 pub struct TypeRep {
     pub ident: Ident,
+    pub vis: syn::Visibility,
     pub generics: Generics,
     pub attrs: OCamlAttributes,
     pub data: TypeRepData,
     pub ocaml_target_type_ident_ts: TokenStream,
+    /// Types declared in the same `type ... and ...` group with `#[ocaml(and = "...")]`
+    pub recursive_group: Vec<Type>,
+    /// Type parameters marked with `#[ocaml(phantom)]`, that don't appear in the representation
+    pub phantom_params: Vec<Ident>,
+}
+
+impl FieldRep {
+    /// Whether the field is an `f64` mapped to an OCaml `float`
+    pub fn is_float(&self) -> bool {
+        let is_f64 = matches!(&self.ty, Type::Path(type_path) if type_path.qself.is_none() && type_path.path.is_ident("f64"));
+        let maps_to_float = match &self.attrs.as_ {
+            None => true,
+            Some(as_) => as_ == "OCamlFloat",
+        };
+        is_f64 && maps_to_float && self.with_path.is_none()
+    }
+
+    /// Whether the field is left out of the OCaml value, with `#[ocaml(skip)]` or
+    /// `#[ocaml(phantom)]`
+    pub fn is_skipped(&self) -> bool {
+        self.attrs.is_skipped() || self.attrs.is_phantom()
+    }
+}
+
+impl VariantRep {
+    /// Whether the fields are stored in a separate record block, see [`RecordKind::Boxed`]
+    pub fn is_boxed_record(&self) -> bool {
+        self.record_kind == RecordKind::Boxed
+    }
+
+    /// Whether the fields are stored in a separate record block that OCaml represents as a
+    /// flat array of unboxed floats, because all of them are `f64`.
+    ///
+    /// Inline records are never stored that way.
+    pub fn is_boxed_float_record(&self) -> bool {
+        self.is_boxed_record()
+            && ocaml_fields(&self.fields).next().is_some()
+            && ocaml_fields(&self.fields).all(|field| field.is_float())
+    }
+}
+
+/// The fields that are part of the OCaml value, that is, the ones not marked with
+/// `#[ocaml(skip)]`
+pub fn ocaml_fields(fields: &[FieldRep]) -> impl Iterator<Item = &FieldRep> {
+    fields.iter().filter(|field| !field.is_skipped())
+}
+
+impl TypeRep {
+    /// All the fields of the type, including the ones of every enum variant
+    pub fn all_fields(&self) -> Box<dyn Iterator<Item = &FieldRep> + '_> {
+        match &self.data {
+            TypeRepData::Struct { fields } => Box::new(fields.iter()),
+            TypeRepData::Enum { variants, .. } => {
+                Box::new(variants.iter().flat_map(|variant| variant.fields.iter()))
+            }
+        }
+    }
+
+    /// Whether the type has type parameters that are part of its representation, that is, not
+    /// marked with `#[ocaml(phantom)]`
+    pub fn has_representation_type_params(&self) -> bool {
+        self.generics
+            .type_params()
+            .any(|param| !self.phantom_params.contains(&param.ident))
+    }
+
+    /// Whether any of the fields that are part of the OCaml value mentions a type parameter
+    /// marked with `#[ocaml(phantom)]`, e.g. `other: Handle<Cap>`
+    pub fn mentions_phantom_params(&self) -> bool {
+        fn mentions(tokens: TokenStream, ident: &Ident) -> bool {
+            tokens.into_iter().any(|token| match token {
+                proc_macro2::TokenTree::Ident(token_ident) => token_ident == *ident,
+                proc_macro2::TokenTree::Group(group) => mentions(group.stream(), ident),
+                _ => false,
+            })
+        }
+
+        self.all_fields()
+            .filter(|field| !field.is_skipped())
+            .any(|field| {
+                let ty = &field.ty;
+                let as_type = &field.ocaml_as_type_override_ts;
+                let tokens = quote! { #ty #as_type };
+                self.phantom_params
+                    .iter()
+                    .any(|param| mentions(tokens.clone(), param))
+            })
+    }
+
+    /// Name under which the extension constructor of `variant_rep` is registered from the OCaml
+    /// side, `registered_name.Constructor` for `#[ocaml(extensible = "registered_name")]` enums,
+    /// where the constructor is the variant name or its `#[ocaml(rename = "...")]`
+    pub fn extension_constructor_name(&self, variant_rep: &VariantRep) -> Option<String> {
+        let constructor = variant_rep
+            .attrs
+            .get_rename()
+            .clone()
+            .unwrap_or_else(|| variant_rep.ident.to_string());
+        self.attrs
+            .get_extensible()
+            .as_ref()
+            .map(|registered_name| format!("{registered_name}.{constructor}"))
+    }
+
+    /// Whether any field refers to the type itself, see [`Recursion`]
+    pub fn is_recursive(&self) -> bool {
+        self.all_fields().any(|field| field.recursion.is_some())
+    }
+
+    /// Whether the type is a record that OCaml represents as a flat array of unboxed
+    /// floats (`Double_array_tag`).
+    ///
+    /// Either explicitly requested with `#[ocaml(float_record)]`, or detected for structs
+    /// with named fields that are all `f64`.
+    pub fn is_float_record(&self) -> bool {
+        if self.attrs.is_float_record() {
+            return true;
+        }
+        if self.attrs.is_unboxed() {
+            return false;
+        }
+        match &self.data {
+            TypeRepData::Struct { fields } => {
+                fields.iter().all(|field| field.ident.is_some())
+                    && ocaml_fields(fields).next().is_some()
+                    && ocaml_fields(fields).all(|field| field.is_float())
+            }
+            TypeRepData::Enum { .. } => false,
+        }
+    }
 }
 
 // --- Parse Stage ---
@@ -89,15 +256,39 @@ pub fn parse_field(field: &syn::Field) -> Result<FieldRep> {
         None
     };
 
+    let with_path = attrs
+        .get_with()
+        .as_ref()
+        .map(|with| parse_path_attribute("with", with, field))
+        .transpose()?;
+
+    let default_path = match attrs.get_default() {
+        Some(FieldDefault::Path(path)) => Some(parse_path_attribute("default", path, field)?),
+        _ => None,
+    };
+
     Ok(FieldRep {
         ident: field.ident.clone(),
         ty: field.ty.clone(),
         attrs,
         ocaml_as_type_override_ts,
+        with_path,
+        default_path,
+        recursion: None,
         span: field.span(),
     })
 }
 
+fn parse_path_attribute(attr_name: &str, path: &str, field: &syn::Field) -> Result<syn::Path> {
+    syn::parse_str::<syn::Path>(path).map_err(|e| {
+        OCamlInteropError::attribute_error_spanned(
+            Some(attr_name),
+            format!("Failed to parse path '{path}': {e}"),
+            field,
+        )
+    })
+}
+
 fn parse_fields_common(fields: &syn::Fields) -> Result<Vec<FieldRep>> {
     match fields {
         Fields::Named(named_fields) => named_fields
@@ -137,11 +328,18 @@ fn parse_enum_variant(variant: &syn::Variant) -> Result<VariantRep> {
         Fields::Unit => VariantKind::Unit,
     };
 
+    let record_kind = if variant_attrs.is_boxed_record() {
+        RecordKind::Boxed
+    } else {
+        RecordKind::Inline
+    };
+
     Ok(VariantRep {
         ident: variant.ident.clone(),
         fields: fields_rep,
         attrs: variant_attrs,
         kind: variant_kind,
+        record_kind,
         span: variant.span(),
     })
 }
@@ -154,6 +352,8 @@ fn parse_struct_data(data_struct: &syn::DataStruct) -> Result<TypeRepData> {
 fn parse_enum_data(data_enum: &syn::DataEnum, type_attrs: &OCamlAttributes) -> Result<TypeRepData> {
     let enum_kind = if type_attrs.is_polymorphic_variant() {
         EnumKind::Polymorphic
+    } else if type_attrs.get_extensible().is_some() {
+        EnumKind::Extensible
     } else {
         EnumKind::Regular
     };
@@ -170,9 +370,10 @@ fn parse_enum_data(data_enum: &syn::DataEnum, type_attrs: &OCamlAttributes) -> R
     })
 }
 
-pub fn parse_input(derive_input: DeriveInput) -> Result<TypeRep> {
+pub fn parse_input(mut derive_input: DeriveInput) -> Result<TypeRep> {
     let type_ident = derive_input.ident.clone();
     let type_attrs = OCamlAttributes::from_attrs(&derive_input.attrs)?;
+    let phantom_params = take_phantom_params(&mut derive_input.generics)?;
 
     let ocaml_target_type_ident_ts = match &type_attrs.as_ {
         Some(as_val_str) => as_val_str.parse::<TokenStream>().map_err(|e| {
@@ -188,7 +389,27 @@ pub fn parse_input(derive_input: DeriveInput) -> Result<TypeRep> {
         }
     };
 
-    let type_rep_data = match &derive_input.data {
+    let recursive_group = type_attrs
+        .get_and()
+        .as_ref()
+        .map(|types| {
+            types
+                .split(',')
+                .map(|ty| {
+                    syn::parse_str::<Type>(ty.trim()).map_err(|e| {
+                        OCamlInteropError::attribute_error_spanned(
+                            Some("and"),
+                            format!("Failed to parse type '{}': {e}", ty.trim()),
+                            &derive_input.ident,
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut type_rep_data = match &derive_input.data {
         Data::Struct(data_struct) => parse_struct_data(data_struct)?,
         Data::Enum(data_enum) => parse_enum_data(data_enum, &type_attrs)?,
         Data::Union(data_union) => {
@@ -199,15 +420,127 @@ pub fn parse_input(derive_input: DeriveInput) -> Result<TypeRep> {
         }
     };
 
+    // Generic types keep converting recursive fields with recursive calls, the stack of
+    // pending values needs a concrete type
+    let supports_recursion = derive_input.generics.type_params().next().is_none()
+        && !type_attrs.is_unboxed()
+        && !type_attrs.is_polymorphic_variant()
+        && type_attrs.get_extensible().is_none()
+        && !type_attrs.is_tuple();
+    if supports_recursion {
+        mark_recursive_fields(&type_ident, &mut type_rep_data);
+    }
+
+    // Tuple structs marked with `#[ocaml(tuple)]` map to the OCaml tuple of their fields
+    let ocaml_target_type_ident_ts = match &type_rep_data {
+        TypeRepData::Struct { fields } if type_attrs.is_tuple() => {
+            let field_types = ocaml_fields(fields)
+                .map(|field_rep| get_ocaml_type(field_rep, TypeDirection::ToOCaml));
+            quote! { (#(#field_types),*) }
+        }
+        _ => ocaml_target_type_ident_ts,
+    };
+
     Ok(TypeRep {
         ident: type_ident,
+        vis: derive_input.vis,
         generics: derive_input.generics,
         attrs: type_attrs,
         data: type_rep_data,
         ocaml_target_type_ident_ts,
+        recursive_group,
+        phantom_params,
     })
 }
 
+/// Collects the type parameters marked with `#[ocaml(phantom)]`, removing the `#[ocaml(...)]`
+/// attributes from the generics so that they can be used in the generated impls
+fn take_phantom_params(generics: &mut Generics) -> Result<Vec<Ident>> {
+    let mut phantom_params = Vec::new();
+    for param in generics.type_params_mut() {
+        let attrs = OCamlAttributes::from_attrs(&param.attrs)?;
+        if attrs.is_phantom() {
+            phantom_params.push(param.ident.clone());
+        }
+        param.attrs.retain(|attr| !attr.path().is_ident("ocaml"));
+    }
+    Ok(phantom_params)
+}
+
+/// Sets [`FieldRep::recursion`] for the fields that refer to the type `type_ident` itself
+fn mark_recursive_fields(type_ident: &Ident, data: &mut TypeRepData) {
+    let fields: Box<dyn Iterator<Item = &mut FieldRep>> = match data {
+        TypeRepData::Struct { fields } => Box::new(fields.iter_mut()),
+        TypeRepData::Enum { variants, .. } => Box::new(
+            variants
+                .iter_mut()
+                .flat_map(|variant| variant.fields.iter_mut()),
+        ),
+    };
+    for field in fields {
+        if field.is_skipped() || field.with_path.is_some() {
+            continue;
+        }
+        field.recursion = if is_box_of(&field.ty, type_ident) {
+            Some(Recursion::Boxed)
+        } else if single_type_argument(&field.ty, "Option")
+            .is_some_and(|ty| is_box_of(ty, type_ident))
+        {
+            Some(Recursion::OptionalBoxed)
+        } else if single_type_argument(&field.ty, "Vec").is_some_and(|ty| is_self(ty, type_ident))
+            && maps_to_list(field)
+        {
+            Some(Recursion::List)
+        } else {
+            None
+        };
+    }
+}
+
+/// Whether the field is converted to an OCaml list, the default for `Vec`, unless overridden
+/// with `#[ocaml(as_ = "...")]` (e.g. to an `OCamlUniformArray`)
+fn maps_to_list(field: &FieldRep) -> bool {
+    match &field.ocaml_as_type_override_ts {
+        Some(ts) => syn::parse2::<Type>(ts.clone())
+            .is_ok_and(|ty| single_type_argument(&ty, "OCamlList").is_some()),
+        None => true,
+    }
+}
+
+/// The only type argument of `ty` if it is a path to the type `name`, e.g. `T` for `Box<T>`
+fn single_type_argument<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if type_path.qself.is_some() || segment.ident != name {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `ty` is `Box<T>`, where `T` is `Self` or `type_ident`
+fn is_box_of(ty: &Type, type_ident: &Ident) -> bool {
+    single_type_argument(ty, "Box").is_some_and(|ty| is_self(ty, type_ident))
+}
+
+/// Whether `ty` is `Self` or `type_ident`
+fn is_self(ty: &Type, type_ident: &Ident) -> bool {
+    match ty {
+        Type::Path(type_path) => {
+            type_path.qself.is_none()
+                && (type_path.path.is_ident(type_ident) || type_path.path.is_ident("Self"))
+        }
+        _ => false,
+    }
+}
+
 // --- Shared Type Parsing Utilities ---
 
 /// Maps a Rust primitive type to the corresponding OCaml interop primitive type
@@ -269,7 +602,7 @@ pub fn extract_inner_type_from_path(
     }
 }
 
-/// Processes a path type (like primitives, OCaml<T>, BoxRoot<T>)
+/// Processes a path type (like primitives, OCaml<T>, BoxRoot<T>, OCamlCallback<Args, Ret>)
 pub fn process_path_type(
     type_path: &syn::TypePath,
     user_type: &syn::Type,
@@ -309,10 +642,21 @@ pub fn process_path_type(
             let inner_type = extract_inner_type_from_path(type_path, "BoxRoot")?;
             Ok(InteropTypeDetail::BoxRoot { inner_type })
         }
+        "OCamlCallback" => {
+            if !matches!(last_segment.arguments, PathArguments::AngleBracketed(_)) {
+                return Err(syn::Error::new_spanned(
+                    &last_segment.arguments,
+                    "OCamlCallback<Args, Ret> type arguments must be angle bracketed",
+                ));
+            }
+            Ok(InteropTypeDetail::Callback {
+                wrapper_type: Box::new(user_type.clone()),
+            })
+        }
         _ => Err(syn::Error::new_spanned(
             user_type,
             format!(
-                "Unsupported type identifier: `{ident_str}`. Must be a primitive, OCaml<T>, or BoxRoot<T> (for arguments).",
+                "Unsupported type identifier: `{ident_str}`. Must be a primitive, OCaml<T>, or BoxRoot<T>/OCamlCallback<Args, Ret> (for arguments).",
             ),
         )),
     }
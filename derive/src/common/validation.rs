@@ -3,7 +3,9 @@
 
 use proc_macro2::Span;
 
-use crate::common::parsing::{EnumKind, FieldRep, TypeRep, TypeRepData, VariantRep};
+use crate::common::parsing::{
+    ocaml_fields, EnumKind, FieldRep, TypeRep, TypeRepData, VariantKind, VariantRep,
+};
 use crate::common::{OCamlInteropError, Result};
 
 /// Creates an error for when an attribute is used in the wrong context
@@ -40,6 +42,137 @@ pub fn validate_polymorphic_kind(type_rep: &TypeRep) -> Result<()> {
     Ok(())
 }
 
+/// Validates that `#[ocaml(extensible = "...")]` is only used on enums that are not polymorphic
+/// variants
+pub fn validate_extensible(type_rep: &TypeRep) -> Result<()> {
+    if type_rep.attrs.get_extensible().is_none() {
+        return Ok(());
+    }
+    if !matches!(&type_rep.data, TypeRepData::Enum { .. }) {
+        return Err(invalid_attribute_use(
+            "extensible",
+            "non-enum type",
+            "enum types only",
+            type_rep.ident.span(),
+        ));
+    }
+    if type_rep.attrs.is_polymorphic_variant() {
+        return Err(OCamlInteropError::validation_error(
+            "#[ocaml(extensible = \"...\")] can't be combined with #[ocaml(polymorphic_variant)]",
+            type_rep.ident.span(),
+            Some("attribute validation: extensible"),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates that `#[ocaml(float_record)]` is only used on structs with named `f64` fields
+pub fn validate_float_record(type_rep: &TypeRep) -> Result<()> {
+    if !type_rep.attrs.is_float_record() {
+        return Ok(());
+    }
+    let fields = match &type_rep.data {
+        TypeRepData::Struct { fields } if fields.iter().all(|f| f.ident.is_some()) => fields,
+        _ => {
+            return Err(invalid_attribute_use(
+                "float_record",
+                "a type that is not a struct with named fields",
+                "structs with named fields",
+                type_rep.ident.span(),
+            ));
+        }
+    };
+    if ocaml_fields(fields).next().is_none() {
+        return Err(OCamlInteropError::validation_error(
+            "a float record must have at least one field",
+            type_rep.ident.span(),
+            Some("attribute validation: float_record"),
+        ));
+    }
+    if let Some(field) = ocaml_fields(fields).find(|f| !f.is_float()) {
+        return Err(OCamlInteropError::validation_error(
+            "all the fields of a float record must be `f64` values mapped to OCaml floats",
+            field.span,
+            Some("attribute validation: float_record"),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates that `#[ocaml(unboxed)]` is only used on types with a single field: structs with
+/// one field, and enums with a single variant with one field
+pub fn validate_unboxed(type_rep: &TypeRep) -> Result<()> {
+    if !type_rep.attrs.is_unboxed() {
+        return Ok(());
+    }
+    let is_single_field = match &type_rep.data {
+        TypeRepData::Struct { fields } => ocaml_fields(fields).count() == 1,
+        TypeRepData::Enum {
+            variants,
+            kind: EnumKind::Regular,
+        } => variants.len() == 1 && ocaml_fields(&variants[0].fields).count() == 1,
+        TypeRepData::Enum {
+            kind: EnumKind::Polymorphic | EnumKind::Extensible,
+            ..
+        } => false,
+    };
+    if !is_single_field {
+        return Err(invalid_attribute_use(
+            "unboxed",
+            format!("'{}'", type_rep.ident),
+            "structs with a single field and enums with a single variant with a single field",
+            type_rep.ident.span(),
+        ));
+    }
+    if type_rep.attrs.is_float_record() {
+        return Err(OCamlInteropError::validation_error(
+            "#[ocaml(unboxed)] can't be combined with #[ocaml(float_record)]",
+            type_rep.ident.span(),
+            Some("attribute validation: unboxed"),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates that `#[ocaml(tuple)]` is only used on non-generic tuple structs with at least two
+/// fields, whose OCaml type is the tuple of the field types
+pub fn validate_tuple(type_rep: &TypeRep) -> Result<()> {
+    if !type_rep.attrs.is_tuple() {
+        return Ok(());
+    }
+    let is_tuple_struct = match &type_rep.data {
+        TypeRepData::Struct { fields } => {
+            fields.iter().all(|f| f.ident.is_none()) && ocaml_fields(fields).count() >= 2
+        }
+        TypeRepData::Enum { .. } => false,
+    };
+    if !is_tuple_struct || type_rep.generics.type_params().next().is_some() {
+        return Err(invalid_attribute_use(
+            "tuple",
+            format!("'{}'", type_rep.ident),
+            "non-generic tuple structs with at least two fields",
+            type_rep.ident.span(),
+        ));
+    }
+    let conflicting_attribute = if type_rep.attrs.as_.is_some() {
+        Some("#[ocaml(as_ = \"...\")]")
+    } else if type_rep.attrs.is_unboxed() {
+        Some("#[ocaml(unboxed)]")
+    } else if type_rep.attrs.is_float_record() {
+        Some("#[ocaml(float_record)]")
+    } else {
+        None
+    };
+    if let Some(attribute) = conflicting_attribute {
+        return Err(OCamlInteropError::validation_error(
+            format!("#[ocaml(tuple)] can't be combined with {attribute}"),
+            type_rep.ident.span(),
+            Some("attribute validation: tuple"),
+        ));
+    }
+    Ok(())
+}
+
 /// Validates that the `#[ocaml(tag="...")]` attribute is only used on polymorphic enum variants
 pub fn validate_tag_attribute(variant_rep: &VariantRep, enum_kind: EnumKind) -> Result<()> {
     if let Some(tag) = &variant_rep.attrs.tag {
@@ -55,6 +188,92 @@ pub fn validate_tag_attribute(variant_rep: &VariantRep, enum_kind: EnumKind) ->
     Ok(())
 }
 
+/// Validates that `#[ocaml(inline_record)]` and `#[ocaml(boxed_record)]` are only used on
+/// struct variants of regular enums, and not together
+pub fn validate_record_kind(
+    variant_rep: &VariantRep,
+    enum_kind: EnumKind,
+    type_rep: &TypeRep,
+) -> Result<()> {
+    let attrs = &variant_rep.attrs;
+    if !attrs.is_inline_record() && !attrs.is_boxed_record() {
+        return Ok(());
+    }
+    let attr_name = if attrs.is_boxed_record() {
+        "boxed_record"
+    } else {
+        "inline_record"
+    };
+    if enum_kind != EnumKind::Regular || variant_rep.kind != VariantKind::Struct {
+        return Err(invalid_attribute_use(
+            attr_name,
+            format!("variant '{}'", variant_rep.ident),
+            "variants with named fields of regular enums",
+            variant_rep.span,
+        ));
+    }
+    if attrs.is_inline_record() && attrs.is_boxed_record() {
+        return Err(OCamlInteropError::validation_error(
+            "#[ocaml(inline_record)] and #[ocaml(boxed_record)] can't be used together",
+            variant_rep.span,
+            Some("attribute validation: boxed_record"),
+        ));
+    }
+    if attrs.is_boxed_record() && type_rep.attrs.is_unboxed() {
+        return Err(OCamlInteropError::validation_error(
+            "#[ocaml(boxed_record)] can't be used in an #[ocaml(unboxed)] enum",
+            variant_rep.span,
+            Some("attribute validation: boxed_record"),
+        ));
+    }
+    Ok(())
+}
+
+/// Validates that `#[ocaml(mutable)]` is only used on the named fields of non-generic structs
+/// that are stored in a block (not `[@@unboxed]`)
+pub fn validate_mutable_fields(type_rep: &TypeRep) -> Result<()> {
+    match &type_rep.data {
+        TypeRepData::Struct { fields } => {
+            let Some(field) = fields.iter().find(|f| f.attrs.is_mutable()) else {
+                return Ok(());
+            };
+            let context = if field.ident.is_none() {
+                Some("a tuple struct field")
+            } else if field.is_skipped() {
+                Some("a field marked with #[ocaml(skip)]")
+            } else if type_rep.attrs.is_unboxed() {
+                Some("a field of an #[ocaml(unboxed)] struct")
+            } else if type_rep.has_representation_type_params() {
+                Some("a field of a generic struct")
+            } else {
+                None
+            };
+            match context {
+                Some(context) => Err(invalid_attribute_use(
+                    "mutable",
+                    context,
+                    "named fields of non-generic structs",
+                    field.span,
+                )),
+                None => Ok(()),
+            }
+        }
+        TypeRepData::Enum { variants, .. } => {
+            for variant in variants {
+                if let Some(field) = variant.fields.iter().find(|f| f.attrs.is_mutable()) {
+                    return Err(invalid_attribute_use(
+                        "mutable",
+                        format!("a field of variant '{}'", variant.ident),
+                        "named fields of non-generic structs",
+                        field.span,
+                    ));
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
 /// Validates field attributes
 pub fn validate_field_attributes(field_rep: &FieldRep) -> Result<()> {
     // Check that as_ attribute was successfully parsed if present
@@ -63,11 +282,134 @@ pub fn validate_field_attributes(field_rep: &FieldRep) -> Result<()> {
         // Error should have been generated during parse_field already.
     }
 
-    // Additional field validation could be added here
+    if field_rep.attrs.get_default().is_some() && !field_rep.is_skipped() {
+        return Err(invalid_attribute_use(
+            "default",
+            "a field that is not skipped",
+            "fields marked with #[ocaml(skip)]",
+            field_rep.span,
+        ));
+    }
+
+    if field_rep.is_skipped() && field_rep.with_path.is_some() {
+        return Err(OCamlInteropError::validation_error(
+            "#[ocaml(with = \"...\")] can't be used on a field marked with #[ocaml(skip)]",
+            field_rep.span,
+            Some("attribute validation: with"),
+        ));
+    }
+
+    if field_rep.attrs.is_phantom() {
+        let is_phantom_data = matches!(
+            &field_rep.ty,
+            syn::Type::Path(type_path) if type_path.path.segments.last().is_some_and(|segment| segment.ident == "PhantomData")
+        );
+        if !is_phantom_data {
+            return Err(invalid_attribute_use(
+                "phantom",
+                "a field that is not a PhantomData",
+                "PhantomData fields and type parameters",
+                field_rep.span,
+            ));
+        }
+        if field_rep.with_path.is_some() || field_rep.attrs.get_default().is_some() {
+            return Err(OCamlInteropError::validation_error(
+                "#[ocaml(with = \"...\")] and #[ocaml(default)] can't be used on a field marked with #[ocaml(phantom)]",
+                field_rep.span,
+                Some("attribute validation: phantom"),
+            ));
+        }
+    }
+
+    if field_rep.attrs.get_and().is_some() {
+        return Err(invalid_attribute_use(
+            "and",
+            "a field",
+            "structs and enums",
+            field_rep.span,
+        ));
+    }
 
     Ok(())
 }
 
+/// Validates that the fields of a variant can be converted: polymorphic variants don't support
+/// `#[ocaml(skip)]` or `#[ocaml(with = "...")]` fields, and variants with fields must keep at
+/// least one of them
+pub fn validate_variant_fields(variant_rep: &VariantRep, enum_kind: EnumKind) -> Result<()> {
+    if enum_kind == EnumKind::Polymorphic {
+        if let Some(field) = variant_rep
+            .fields
+            .iter()
+            .find(|f| f.is_skipped() || f.with_path.is_some())
+        {
+            let attr_name = if field.attrs.is_phantom() {
+                "phantom"
+            } else if field.is_skipped() {
+                "skip"
+            } else {
+                "with"
+            };
+            return Err(invalid_attribute_use(
+                attr_name,
+                format!(
+                    "a field of variant '{}' in a polymorphic enum",
+                    variant_rep.ident
+                ),
+                "fields of structs and of regular enum variants",
+                field.span,
+            ));
+        }
+        if variant_rep.attrs.get_rename().is_some() {
+            return Err(invalid_attribute_use(
+                "rename",
+                format!("variant '{}' in a polymorphic enum", variant_rep.ident),
+                "variants of regular enums (use #[ocaml(tag = \"...\")] for polymorphic variants)",
+                variant_rep.span,
+            ));
+        }
+    }
+
+    if !variant_rep.fields.is_empty() && ocaml_fields(&variant_rep.fields).next().is_none() {
+        return Err(OCamlInteropError::validation_error(
+            format!(
+                "variant '{}' must keep at least one field that is not marked with #[ocaml(skip)]",
+                variant_rep.ident
+            ),
+            variant_rep.span,
+            Some("attribute validation: skip"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validates that the type parameters marked with `#[ocaml(phantom)]` aren't the type of a
+/// field that is part of the OCaml value
+pub fn validate_phantom_params(type_rep: &TypeRep) -> Result<()> {
+    for field in type_rep.all_fields().filter(|field| !field.is_skipped()) {
+        let as_type = field
+            .ocaml_as_type_override_ts
+            .as_ref()
+            .and_then(|as_type| syn::parse2::<syn::Type>(as_type.clone()).ok());
+        let phantom_param = type_rep.phantom_params.iter().find(|param| {
+            std::iter::once(&field.ty).chain(as_type.as_ref()).any(
+                |ty| matches!(ty, syn::Type::Path(type_path) if type_path.path.is_ident(*param)),
+            )
+        });
+        if let Some(param) = phantom_param {
+            return Err(OCamlInteropError::validation_error(
+                format!(
+                    "phantom type parameter '{param}' can't be the type of a field that is part of the OCaml value"
+                ),
+                field.span,
+                Some("attribute validation: phantom"),
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Validates that enums have at least one variant
 pub fn validate_enum_has_variants(type_rep: &TypeRep) -> Result<()> {
     if let TypeRepData::Enum { variants, .. } = &type_rep.data {
@@ -82,10 +424,41 @@ pub fn validate_enum_has_variants(type_rep: &TypeRep) -> Result<()> {
     Ok(())
 }
 
+/// Validates that `#[derive(OCamlAccessors)]` is used on a non-generic struct with fields
+pub fn validate_accessors_target(type_rep: &TypeRep) -> Result<()> {
+    let context = match &type_rep.data {
+        TypeRepData::Enum { .. } => Some("accessors can only be derived for structs"),
+        TypeRepData::Struct { .. } if type_rep.has_representation_type_params() => {
+            Some("accessors can't be derived for generic structs")
+        }
+        TypeRepData::Struct { fields } if ocaml_fields(fields).next().is_none() => {
+            Some("accessors require at least one field that is not skipped")
+        }
+        TypeRepData::Struct { .. } => None,
+    };
+    match context {
+        Some(context) => Err(OCamlInteropError::validation_error(
+            format!(
+                "#[derive(OCamlAccessors)] is not supported on '{}'",
+                type_rep.ident
+            ),
+            type_rep.ident.span(),
+            Some(context),
+        )),
+        None => Ok(()),
+    }
+}
+
 /// General validation for a type representation
 pub fn validate_type_rep(type_rep: &TypeRep) -> Result<()> {
     // 1. Validate container-level attributes
     validate_polymorphic_kind(type_rep)?;
+    validate_extensible(type_rep)?;
+    validate_float_record(type_rep)?;
+    validate_unboxed(type_rep)?;
+    validate_tuple(type_rep)?;
+    validate_mutable_fields(type_rep)?;
+    validate_phantom_params(type_rep)?;
 
     // 2. Validate that enums have variants
     validate_enum_has_variants(type_rep)?;
@@ -95,6 +468,8 @@ pub fn validate_type_rep(type_rep: &TypeRep) -> Result<()> {
         TypeRepData::Enum { variants, kind, .. } => {
             for variant in variants {
                 validate_tag_attribute(variant, *kind)?;
+                validate_variant_fields(variant, *kind)?;
+                validate_record_kind(variant, *kind, type_rep)?;
 
                 for field in &variant.fields {
                     validate_field_attributes(field)?;
@@ -47,6 +47,15 @@ fn process_function_arguments(
                 };
                 initializations.push(init_part);
             }
+            InteropTypeDetail::Callback { wrapper_type } => {
+                let sig_part = quote_spanned! {original_rust_ty.span()=> #original_pat: ::ocaml_interop::RawOCaml };
+                extern_c_fn_params_sig_parts.push(sig_part);
+
+                let init_part = quote_spanned! {original_rust_ty.span()=>
+                    let #original_pat : #original_rust_ty = unsafe { <#wrapper_type>::from_raw(#original_pat) };
+                };
+                initializations.push(init_part);
+            }
             InteropTypeDetail::Unit => {
                 // This should be caught during parsing
                 return Err(Error::new_spanned(
@@ -99,6 +108,13 @@ fn process_return_type(data: &ExportedFnData) -> Result<(TokenStream, TokenStrea
                 "Internal error: BoxRoot<T> should not be possible as a return type in expansion phase.",
             ))
         }
+        InteropTypeDetail::Callback { .. } => {
+            // This should have been caught by validation
+            Err(Error::new_spanned(
+                user_return_type_ast,
+                "Internal error: OCamlCallback<Args, Ret> should not be possible as a return type in expansion phase.",
+            ))
+        }
     }
 }
 
@@ -120,7 +136,9 @@ fn process_bytecode_argument(
     });
 
     match &p_arg.type_detail {
-        InteropTypeDetail::OCaml { .. } | InteropTypeDetail::BoxRoot { .. } => {
+        InteropTypeDetail::OCaml { .. }
+        | InteropTypeDetail::BoxRoot { .. }
+        | InteropTypeDetail::Callback { .. } => {
             preparations.push(quote! {
                 let #target_var_ident = #raw_val_ident;
             });
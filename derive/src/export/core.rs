@@ -61,6 +61,10 @@ pub(crate) enum InteropTypeDetail {
         // Only for arguments
         inner_type: Box<syn::Type>, // e.g., syn::Type for "MyStruct"
     },
+    Callback {
+        // Only for arguments, e.g., syn::Type for "OCamlCallback<(OCamlInt,), String>"
+        wrapper_type: Box<syn::Type>,
+    },
 }
 
 impl InteropTypeDetail {
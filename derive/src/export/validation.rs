@@ -121,6 +121,12 @@ fn validate_noalloc_arguments(data: &ExportedFnData) -> Result<(), Error> {
                 "`BoxRoot<T>` arguments are not allowed when `noalloc` is used because BoxRoot implies allocation for rooting.",
             ));
         }
+        if let InteropTypeDetail::Callback { .. } = &arg.type_detail {
+            return Err(Error::new_spanned(
+                &arg.original_rust_type,
+                "`OCamlCallback<Args, Ret>` arguments are not allowed when `noalloc` is used because callbacks are rooted and can allocate when called.",
+            ));
+        }
     }
 
     Ok(())
@@ -135,6 +141,13 @@ fn validate_return_type(data: &ExportedFnData) -> Result<(), Error> {
             "BoxRoot<T> cannot be used as a return type directly. Return OCaml<T> instead.",
         ));
     }
+    // Validate that OCamlCallback<Args, Ret> is not used as a return type
+    if let InteropTypeDetail::Callback { .. } = &data.return_interop_detail {
+        return Err(Error::new_spanned(
+            &data.user_return_type_ast,
+            "OCamlCallback<Args, Ret> cannot be used as a return type. Return OCaml<fn(A) -> R> instead.",
+        ));
+    }
 
     Ok(())
 }
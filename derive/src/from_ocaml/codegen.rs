@@ -7,10 +7,13 @@ use syn::{DeriveInput, Ident};
 
 use crate::common::{
     field_processing::{
+        extension_constructor_expr, field_default_expr, field_from_ocaml_expr,
         generate_struct_field_extractions, get_ocaml_type, make_field_var, TypeDirection,
     },
     format_type,
-    parsing::{parse_input, EnumKind, TypeRep, TypeRepData, VariantKind},
+    parsing::{
+        ocaml_fields, parse_input, EnumKind, FieldRep, Recursion, TypeRep, TypeRepData, VariantKind,
+    },
     polytag_utils::generate_polytag_hash,
     validation, OCamlInteropError, Result,
 };
@@ -182,6 +185,45 @@ fn codegen_struct_impl(
     let (impl_generics, ty_generics, where_clause) = type_rep.generics.split_for_impl();
     let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
 
+    if type_rep.attrs.is_unboxed() {
+        let body = generate_unboxed_body(quote! { Self }, fields);
+        return Ok(quote! {
+            unsafe impl #impl_generics ::ocaml_interop::FromOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<#ocaml_type_ident_token_stream #ty_generics>) -> Self {
+                    #body
+                }
+            }
+        });
+    }
+
+    if type_rep.is_float_record() {
+        let mut ocaml_idx = 0usize;
+        let field_extractions = fields.iter().map(|field_rep| {
+            let field_ident = field_rep
+                .ident
+                .as_ref()
+                .expect("Float record fields must have identifiers");
+            if field_rep.is_skipped() {
+                let default = field_default_expr(field_rep);
+                return quote! { #field_ident: #default };
+            }
+            let idx_lit =
+                syn::LitInt::new(&format!("{ocaml_idx}usize"), proc_macro2::Span::call_site());
+            ocaml_idx += 1;
+            quote! {
+                #field_ident: unsafe { ::ocaml_interop::internal::double_field(raw, #idx_lit) }
+            }
+        });
+        return Ok(quote! {
+            unsafe impl #impl_generics ::ocaml_interop::FromOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<#ocaml_type_ident_token_stream #ty_generics>) -> Self {
+                    let raw = unsafe { v.raw() };
+                    Self { #(#field_extractions),* }
+                }
+            }
+        });
+    }
+
     let field_processing_quotes = generate_struct_field_extractions(fields, quote! { v });
 
     let struct_constructor = if fields.iter().all(|f| f.ident.is_some()) {
@@ -201,13 +243,20 @@ fn codegen_struct_impl(
         quote! { Self(#(#field_vars),*) }
     };
 
+    let body = wrap_recursive_conversion(
+        type_rep,
+        quote! {
+            unsafe {
+                #(#field_processing_quotes)*
+                #struct_constructor
+            }
+        },
+    );
+
     Ok(quote! {
         unsafe impl #impl_generics ::ocaml_interop::FromOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
             fn from_ocaml(v: ::ocaml_interop::OCaml<#ocaml_type_ident_token_stream #ty_generics>) -> Self {
-                unsafe {
-                    #(#field_processing_quotes)*
-                    #struct_constructor
-                }
+                #body
             }
         }
     })
@@ -304,6 +353,145 @@ fn generate_polymorphic_enum_body(
     })
 }
 
+/// Generates the statements that convert the fields of a variant with arguments, stored in `v`
+/// from the field `first_field_idx` on, and build the variant
+fn generate_block_variant_conversion(
+    variant_rep: &crate::common::parsing::VariantRep,
+    first_field_idx: usize,
+) -> TokenStream {
+    let variant_ident = &variant_rep.ident;
+    let fields = &variant_rep.fields;
+
+    // Boxed records are stored in a separate block, in the only argument of the
+    // constructor
+    let record_idx = syn::LitInt::new(&first_field_idx.to_string(), proc_macro2::Span::call_site());
+    let record_prelude = if variant_rep.is_boxed_float_record() {
+        quote! { let record = unsafe { v.field::<()>(#record_idx).raw() }; }
+    } else if variant_rep.is_boxed_record() {
+        quote! { let record = unsafe { v.field::<()>(#record_idx) }; }
+    } else {
+        quote! {}
+    };
+    let source = if variant_rep.is_boxed_record() {
+        quote! { record }
+    } else {
+        quote! { v }
+    };
+
+    let mut ocaml_idx = if variant_rep.is_boxed_record() {
+        0
+    } else {
+        first_field_idx
+    };
+    let field_processing = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field_rep)| {
+            let field_var_ident = field_rep
+                .ident
+                .as_ref()
+                .cloned()
+                .unwrap_or_else(|| make_field_var(idx, None));
+            if field_rep.is_skipped() {
+                let default = field_default_expr(field_rep);
+                return quote! {
+                    let #field_var_ident = #default;
+                };
+            }
+            if variant_rep.is_boxed_float_record() {
+                let idx_lit = syn::LitInt::new(
+                    &format!("{ocaml_idx}usize"),
+                    proc_macro2::Span::call_site(),
+                );
+                ocaml_idx += 1;
+                return quote! {
+                    let #field_var_ident = unsafe { ::ocaml_interop::internal::double_field(record, #idx_lit) };
+                };
+            }
+            let ocaml_type = get_ocaml_type(field_rep, TypeDirection::FromOCaml);
+            let idx_lit = syn::LitInt::new(
+                &ocaml_idx.to_string(),
+                proc_macro2::Span::call_site(),
+            );
+            ocaml_idx += 1;
+            let conversion = field_from_ocaml_expr(
+                field_rep,
+                quote! { #source.field::<#ocaml_type>(#idx_lit) },
+            );
+
+            if field_rep.recursion == Some(Recursion::Boxed) {
+                // Doesn't read the OCaml value, see `wrap_recursive_conversion`
+                return quote! {
+                    let #field_var_ident = #conversion;
+                };
+            }
+            quote! {
+                let #field_var_ident = unsafe { #conversion };
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let constructor = match variant_rep.kind {
+        VariantKind::Tuple => {
+            let field_vars = (0..fields.len()).map(|idx| make_field_var(idx, None));
+            quote! { Self::#variant_ident(#(#field_vars),*) }
+        }
+        VariantKind::Struct => {
+            let field_assignments = fields.iter().map(|field_rep| {
+                field_rep
+                    .ident
+                    .as_ref()
+                    .expect("Struct variant fields must have identifiers")
+            });
+            quote! { Self::#variant_ident { #(#field_assignments),* } }
+        }
+        _ => unreachable!("Unit variants should be handled separately"),
+    };
+
+    quote! {
+        #record_prelude
+        #(#field_processing)*
+        #constructor
+    }
+}
+
+/// Generates the body for an `#[ocaml(extensible = "...")]` enum, that compares the extension
+/// constructor of the value with the ones registered for each variant
+fn generate_extensible_enum_body(
+    type_rep: &TypeRep,
+    variants: &[crate::common::parsing::VariantRep],
+) -> TokenStream {
+    let branches = variants.iter().map(|variant_rep| {
+        let constructor_name = type_rep
+            .extension_constructor_name(variant_rep)
+            .expect("Extensible enums must have a registered name");
+        // The arguments follow the extension constructor in the block
+        let conversion = match variant_rep.kind {
+            VariantKind::Unit => {
+                let variant_ident = &variant_rep.ident;
+                quote! { Self::#variant_ident }
+            }
+            VariantKind::Tuple | VariantKind::Struct => {
+                generate_block_variant_conversion(variant_rep, 1)
+            }
+        };
+        let expected_constructor = extension_constructor_expr(&constructor_name);
+        quote! {
+            if constructor == unsafe { #expected_constructor } {
+                #conversion
+            } else
+        }
+    });
+    let unknown_message = format!("Unknown extension constructor for {}", type_rep.ident);
+
+    quote! {
+        let constructor = unsafe { ::ocaml_interop::internal::extension_constructor_of(v.raw()) };
+        #(#branches)* {
+            panic!(#unknown_message)
+        }
+    }
+}
+
 /// Generates the complete body for a regular enum based on variant composition
 fn generate_regular_enum_body(
     _type_rep: &TypeRep,
@@ -326,50 +514,14 @@ fn generate_regular_enum_body(
                 unit_variant_idx += 1;
             }
             VariantKind::Tuple | VariantKind::Struct => {
-                let variant_ident = &variant_rep.ident;
-                let fields = &variant_rep.fields;
                 let tag_lit = syn::LitInt::new(
                     &block_variant_idx.to_string(),
                     proc_macro2::Span::call_site(),
                 );
-
-                let field_processing = fields
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, field_rep)| {
-                        let field_var_ident = field_rep.ident.as_ref()
-                            .cloned()
-                            .unwrap_or_else(|| make_field_var(idx, None));
-                        let ocaml_type = get_ocaml_type(field_rep, TypeDirection::FromOCaml);
-                        let idx_lit = syn::LitInt::new(&idx.to_string(), proc_macro2::Span::call_site());
-
-                        quote! {
-                            let #field_var_ident = unsafe { v.field::<#ocaml_type>(#idx_lit).to_rust() };
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let constructor = match variant_rep.kind {
-                    VariantKind::Tuple => {
-                        let field_vars = (0..fields.len()).map(|idx| make_field_var(idx, None));
-                        quote! { Self::#variant_ident(#(#field_vars),*) }
-                    }
-                    VariantKind::Struct => {
-                        let field_assignments = fields.iter().map(|field_rep| {
-                            field_rep
-                                .ident
-                                .as_ref()
-                                .expect("Struct variant fields must have identifiers")
-                        });
-                        quote! { Self::#variant_ident { #(#field_assignments),* } }
-                    }
-                    _ => unreachable!("Unit variants should be handled separately"),
-                };
-
+                let conversion = generate_block_variant_conversion(variant_rep, 0);
                 let match_arm = quote! {
                     #tag_lit => {
-                        #(#field_processing)*
-                        #constructor
+                        #conversion
                     }
                 };
 
@@ -405,6 +557,146 @@ fn generate_regular_enum_body(
     })
 }
 
+/// Generate the statements that push the values of the recursive fields stored in `source` to
+/// `stack`, in field order, so that they are converted last field first and popped from
+/// `converted` in field order
+fn generate_child_pushes(
+    ocaml_type_ident: &TokenStream,
+    fields: &[FieldRep],
+    source: TokenStream,
+) -> Vec<TokenStream> {
+    ocaml_fields(fields)
+        .enumerate()
+        .filter_map(|(ocaml_idx, field_rep)| {
+            let idx_lit = syn::LitInt::new(
+                &format!("{ocaml_idx}usize"),
+                proc_macro2::Span::call_site(),
+            );
+            match field_rep.recursion? {
+                Recursion::Boxed => Some(quote! {
+                    stack.push((unsafe { #source.field::<#ocaml_type_ident>(#idx_lit) }, false));
+                }),
+                Recursion::OptionalBoxed => Some(quote! {
+                    if let Some(child) = unsafe { #source.field::<Option<#ocaml_type_ident>>(#idx_lit) }.to_option() {
+                        stack.push((child, false));
+                    }
+                }),
+                Recursion::List => Some(quote! {
+                    let mut list = unsafe { #source.field::<::ocaml_interop::OCamlList<#ocaml_type_ident>>(#idx_lit) };
+                    while let Some((child, tail)) = list.uncons() {
+                        stack.push((child, false));
+                        list = tail;
+                    }
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Wraps `build`, the conversion of `v` once its recursive fields have been converted, in a
+/// loop that converts the values of recursive types with an explicit stack instead of recursive
+/// calls. Values are converted after the values of their recursive fields, which are taken
+/// from `converted`. Non-recursive types are converted by `build` directly.
+fn wrap_recursive_conversion(type_rep: &TypeRep, build: TokenStream) -> TokenStream {
+    if !type_rep.is_recursive() {
+        return build;
+    }
+
+    let ocaml_type_ident = &type_rep.ocaml_target_type_ident_ts;
+    let child_pushes = match &type_rep.data {
+        TypeRepData::Struct { fields } => {
+            let pushes = generate_child_pushes(ocaml_type_ident, fields, quote! { v });
+            quote! { #(#pushes)* }
+        }
+        TypeRepData::Enum { variants, .. } => {
+            let arms = variants
+                .iter()
+                .filter(|variant_rep| variant_rep.kind != VariantKind::Unit)
+                .enumerate()
+                .filter_map(|(block_variant_idx, variant_rep)| {
+                    let tag_lit = syn::LitInt::new(
+                        &block_variant_idx.to_string(),
+                        proc_macro2::Span::call_site(),
+                    );
+                    let (record_prelude, source) = if variant_rep.is_boxed_record() {
+                        (
+                            quote! { let record = unsafe { v.field::<()>(0) }; },
+                            quote! { record },
+                        )
+                    } else {
+                        (quote! {}, quote! { v })
+                    };
+                    let pushes =
+                        generate_child_pushes(ocaml_type_ident, &variant_rep.fields, source);
+                    if pushes.is_empty() {
+                        return None;
+                    }
+                    Some(quote! {
+                        #tag_lit => {
+                            #record_prelude
+                            #(#pushes)*
+                        }
+                    })
+                });
+            quote! {
+                if v.is_block() {
+                    match v.tag_value() {
+                        #(#arms)*
+                        _ => {}
+                    }
+                }
+            }
+        }
+    };
+
+    quote! {
+        let mut stack = vec![(v, false)];
+        let mut converted: Vec<Self> = Vec::new();
+        while let Some((v, children_converted)) = stack.pop() {
+            if children_converted {
+                let value = { #build };
+                converted.push(value);
+            } else {
+                stack.push((v, true));
+                #child_pushes
+            }
+        }
+        converted.pop().expect("the converted value should be the last one left")
+    }
+}
+
+/// Generates the body for an `[@@unboxed]` type, represented directly by its only field,
+/// that builds the value with `constructor` (`Self` or `Self::Variant`)
+fn generate_unboxed_body(
+    constructor: TokenStream,
+    fields: &[crate::common::parsing::FieldRep],
+) -> TokenStream {
+    let field_rep = fields
+        .iter()
+        .find(|field_rep| !field_rep.is_skipped())
+        .expect("Unboxed types must have one field");
+    let ocaml_type = get_ocaml_type(field_rep, TypeDirection::FromOCaml);
+    let conversion =
+        field_from_ocaml_expr(field_rep, quote! { unsafe { v.cast::<#ocaml_type>() } });
+    let values = fields.iter().map(|field_rep| {
+        if field_rep.is_skipped() {
+            field_default_expr(field_rep)
+        } else {
+            quote! { unboxed }
+        }
+    });
+    let constructor = if fields.iter().all(|field_rep| field_rep.ident.is_some()) {
+        let field_idents = fields.iter().map(|field_rep| &field_rep.ident);
+        quote! { #constructor { #(#field_idents: #values),* } }
+    } else {
+        quote! { #constructor(#(#values),*) }
+    };
+    quote! {
+        let unboxed = #conversion;
+        #constructor
+    }
+}
+
 /// Generates FromOCaml implementation for an enum
 fn codegen_enum_impl(
     type_rep: &TypeRep,
@@ -416,9 +708,16 @@ fn codegen_enum_impl(
     let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
 
     let body = match kind {
-        EnumKind::Regular => generate_regular_enum_body(type_rep, variants)?,
+        EnumKind::Regular if type_rep.attrs.is_unboxed() => {
+            let variant_ident = &variants[0].ident;
+            generate_unboxed_body(quote! { Self::#variant_ident }, &variants[0].fields)
+        }
+        EnumKind::Regular => {
+            wrap_recursive_conversion(type_rep, generate_regular_enum_body(type_rep, variants)?)
+        }
 
         EnumKind::Polymorphic => generate_polymorphic_enum_body(type_rep, variants)?,
+        EnumKind::Extensible => generate_extensible_enum_body(type_rep, variants),
     };
 
     Ok(quote! {
@@ -8,6 +8,7 @@ use syn::ItemFn;
 mod common;
 mod export;
 mod from_ocaml;
+mod ocaml_accessors;
 mod ocaml_describer;
 mod to_ocaml;
 
@@ -15,6 +16,7 @@ mod to_ocaml;
 mod tests {
     mod export_tests;
     mod from_ocaml_tests;
+    mod ocaml_accessors_tests;
     mod ocaml_describer_tests;
     mod to_ocaml_tests;
     mod to_ocaml_type_safety_tests;
@@ -76,3 +78,12 @@ pub fn from_ocaml_derive(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|err| err.to_compile_error())
         .into() // Convert proc_macro2::TokenStream back to proc_macro::TokenStream
 }
+
+#[proc_macro_derive(OCamlAccessors, attributes(ocaml))]
+pub fn ocaml_accessors_derive(input: TokenStream) -> TokenStream {
+    // Convert proc_macro::TokenStream to proc_macro2::TokenStream for internal use
+    let input_pm2 = proc_macro2::TokenStream::from(input);
+    ocaml_accessors::codegen::expand_ocaml_accessors(input_pm2)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into() // Convert proc_macro2::TokenStream back to proc_macro::TokenStream
+}
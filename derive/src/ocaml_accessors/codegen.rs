@@ -0,0 +1,109 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{DeriveInput, Ident};
+
+use crate::common::{
+    field_processing::{get_ocaml_type, TypeDirection},
+    parsing::{ocaml_fields, parse_input, FieldRep, TypeRep, TypeRepData},
+    validation, OCamlInteropError, Result,
+};
+
+/// Generates the signature and body of the accessor for the field at `idx`
+fn generate_field_accessor(
+    type_rep: &TypeRep,
+    idx: usize,
+    field_rep: &FieldRep,
+) -> (TokenStream, TokenStream) {
+    let (accessor_ident, doc) = match &field_rep.ident {
+        Some(field_ident) => (
+            field_ident.clone(),
+            format!("Returns the `{field_ident}` field of the value without converting it."),
+        ),
+        None => (
+            Ident::new(&format!("field_{idx}"), Span::call_site()),
+            format!("Returns field {idx} of the value without converting it."),
+        ),
+    };
+    let idx_lit = syn::LitInt::new(&format!("{idx}usize"), Span::call_site());
+
+    let (signature, body) = if type_rep.is_float_record() {
+        // Fields of float records are stored unboxed, there is no OCaml value to point to
+        (
+            quote! { fn #accessor_ident(&self) -> f64 },
+            quote! { unsafe { ::ocaml_interop::internal::double_field(self.raw(), #idx_lit) } },
+        )
+    } else {
+        let ocaml_type = get_ocaml_type(field_rep, TypeDirection::FromOCaml);
+        let body = if type_rep.attrs.is_unboxed() {
+            // The value of an unboxed type is its only field
+            quote! { unsafe { self.cast() } }
+        } else {
+            quote! { unsafe { self.field(#idx_lit) } }
+        };
+        (
+            quote! { fn #accessor_ident(&self) -> ::ocaml_interop::OCaml<'a, #ocaml_type> },
+            body,
+        )
+    };
+
+    (
+        quote! {
+            #[doc = #doc]
+            #signature;
+        },
+        quote! {
+            #signature {
+                #body
+            }
+        },
+    )
+}
+
+fn codegen_accessors(type_rep: &TypeRep, fields: &[FieldRep]) -> TokenStream {
+    let type_ident = &type_rep.ident;
+    let vis = &type_rep.vis;
+    let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
+    let trait_ident = Ident::new(&format!("{type_ident}Accessors"), type_ident.span());
+
+    let (signatures, methods): (Vec<_>, Vec<_>) = ocaml_fields(fields)
+        .enumerate()
+        .map(|(idx, field_rep)| generate_field_accessor(type_rep, idx, field_rep))
+        .unzip();
+
+    let trait_doc = format!(
+        "Accessors for the fields of OCaml `{type_ident}` values, generated by `#[derive(OCamlAccessors)]`."
+    );
+
+    // Only phantom type parameters are allowed, see `validate_accessors_target`
+    let mut impl_generics = type_rep.generics.clone();
+    impl_generics.params.insert(0, syn::parse_quote! { 'a });
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+    let (_, ty_generics, _) = type_rep.generics.split_for_impl();
+
+    quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_ident<'a> {
+            #(#signatures)*
+        }
+
+        impl #impl_generics #trait_ident<'a> for ::ocaml_interop::OCaml<'a, #ocaml_type_ident_token_stream #ty_generics> #where_clause {
+            #(#methods)*
+        }
+    }
+}
+
+pub fn expand_ocaml_accessors(input: TokenStream) -> Result<TokenStream> {
+    let derive_input = syn::parse2::<DeriveInput>(input).map_err(OCamlInteropError::Syn)?;
+    let type_rep = parse_input(derive_input)?;
+
+    validation::validate_type_rep(&type_rep)?;
+    validation::validate_accessors_target(&type_rep)?;
+
+    match &type_rep.data {
+        TypeRepData::Struct { fields } => Ok(codegen_accessors(&type_rep, fields)),
+        TypeRepData::Enum { .. } => unreachable!("validated by validate_accessors_target"),
+    }
+}
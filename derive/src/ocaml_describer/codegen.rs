@@ -6,7 +6,12 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{DeriveInput, Result};
 
-use crate::common::attr_parsing::OCamlAttributes;
+use crate::common::{
+    attr_parsing::OCamlAttributes,
+    field_processing::{get_ocaml_type, TypeDirection},
+    parsing::{self, ocaml_fields, EnumKind, FieldRep, TypeRep, TypeRepData, VariantKind},
+    validation,
+};
 
 /// Add OCamlDescriber trait bound to a where clause for generic type parameters
 fn add_ocaml_describer_bounds(
@@ -58,6 +63,210 @@ fn generate_type_name_code(ocaml_type_name_str: &str, generics: &syn::Generics)
     }
 }
 
+/// Generate the OCaml type name code of a struct marked with `#[ocaml(tuple)]`, the product of
+/// its field types (e.g. `(int * string)`), parenthesized so that it stays a single tuple when used
+/// as a type argument or as a constructor argument
+fn generate_tuple_type_name_code(fields: &[FieldRep]) -> TokenStream {
+    let mut args = Vec::new();
+    let name_fmt = format!("({})", render_product(fields, &mut args));
+    quote! { format!(#name_fmt #(, #args)*) }
+}
+
+/// Argument for a `{}` placeholder that describes the OCaml type of a field
+fn describe_field_type(field_rep: &FieldRep) -> TokenStream {
+    let ocaml_type = get_ocaml_type(field_rep, TypeDirection::ToOCaml);
+    quote! { <#ocaml_type as ocaml_interop::OCamlDescriber>::ocaml_type_name() }
+}
+
+/// Renders the fields as an OCaml product, `{} * {}`, pushing the field types to `args`
+fn render_product(fields: &[FieldRep], args: &mut Vec<TokenStream>) -> String {
+    ocaml_fields(fields)
+        .map(|field_rep| {
+            args.push(describe_field_type(field_rep));
+            "{}".to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(" * ")
+}
+
+/// Renders the fields as an OCaml record, `{{ a : {}; b : {} }}` (with the braces escaped
+/// for `format!`), pushing the field types to `args`
+fn render_record(fields: &[FieldRep], args: &mut Vec<TokenStream>) -> String {
+    let fields = ocaml_fields(fields)
+        .map(|field_rep| {
+            args.push(describe_field_type(field_rep));
+            let field_name = field_rep
+                .attrs
+                .get_rename()
+                .clone()
+                .or_else(|| field_rep.ident.as_ref().map(|ident| ident.to_string()))
+                .expect("Record fields must have identifiers");
+            let mutable = if field_rep.attrs.is_mutable() {
+                "mutable "
+            } else {
+                ""
+            };
+            format!("{mutable}{field_name} : {{}}")
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    format!("{{{{ {fields} }}}}")
+}
+
+/// Renders a constructor with its arguments, `Name of {} * {}` or `Name of {{ a : {} }}`
+fn render_constructor(
+    name: &str,
+    kind: VariantKind,
+    fields: &[FieldRep],
+    args: &mut Vec<TokenStream>,
+) -> String {
+    match kind {
+        VariantKind::Unit => name.to_string(),
+        VariantKind::Tuple => format!("{name} of {}", render_product(fields, args)),
+        VariantKind::Struct => format!("{name} of {}", render_record(fields, args)),
+    }
+}
+
+/// Generate the `ocaml_type_binding` and `ocaml_type_definition` methods, for non-generic types
+/// with a representation
+fn generate_type_definition_code(
+    type_rep: &TypeRep,
+    ocaml_type_name_str: &str,
+) -> Option<TokenStream> {
+    // The fields of generic types are described with the names of the type arguments, only
+    // phantom type parameters can be declared as OCaml type variables
+    if type_rep.has_representation_type_params() || type_rep.mentions_phantom_params() {
+        return None;
+    }
+    // Tuples are anonymous types, without a definition
+    if type_rep.attrs.is_tuple() {
+        return None;
+    }
+
+    let mut args = Vec::new();
+    let body = match &type_rep.data {
+        // The constructors of extensible types are added separately, with `type t += ...`
+        TypeRepData::Enum {
+            kind: EnumKind::Extensible,
+            ..
+        } => return None,
+        TypeRepData::Struct { fields } if ocaml_fields(fields).next().is_none() => return None,
+        TypeRepData::Struct { fields } => {
+            if fields.iter().all(|field_rep| field_rep.ident.is_some()) {
+                render_record(fields, &mut args)
+            } else {
+                let constructor = type_rep.ident.to_string();
+                render_constructor(&constructor, VariantKind::Tuple, fields, &mut args)
+            }
+        }
+        TypeRepData::Enum {
+            variants,
+            kind: EnumKind::Regular,
+        } => {
+            // Boxed records are declared as separate types in the same group, after the
+            // constructors that use them, and named after the type and the constructor so that
+            // they don't collide with other types
+            let mut record_args = Vec::new();
+            let mut records = Vec::new();
+            let constructors = variants
+                .iter()
+                .map(|variant_rep| {
+                    let constructor = variant_rep
+                        .attrs
+                        .get_rename()
+                        .clone()
+                        .unwrap_or_else(|| variant_rep.ident.to_string());
+                    if variant_rep.is_boxed_record() {
+                        let record_name =
+                            format!("{ocaml_type_name_str}_{}", constructor.to_snake_case());
+                        let record = render_record(&variant_rep.fields, &mut record_args);
+                        records.push(format!(" and {record_name} = {record}"));
+                        format!("{constructor} of {record_name}")
+                    } else {
+                        render_constructor(
+                            &constructor,
+                            variant_rep.kind,
+                            &variant_rep.fields,
+                            &mut args,
+                        )
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" | ");
+            args.extend(record_args);
+            format!("{constructors}{}", records.concat())
+        }
+        TypeRepData::Enum {
+            variants,
+            kind: EnumKind::Polymorphic,
+        } => {
+            let tags = variants
+                .iter()
+                .map(|variant_rep| {
+                    let tag = variant_rep
+                        .attrs
+                        .get_tag()
+                        .clone()
+                        .unwrap_or_else(|| variant_rep.ident.to_string());
+                    match variant_rep.kind {
+                        VariantKind::Unit => format!("`{tag}"),
+                        _ => format!(
+                            "`{tag} of {}",
+                            render_product(&variant_rep.fields, &mut args)
+                        ),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" | ");
+            format!("[ {tags} ]")
+        }
+    };
+
+    let unboxed = if type_rep.attrs.is_unboxed() {
+        " [@@unboxed]"
+    } else {
+        ""
+    };
+    let type_variables = type_rep
+        .phantom_params
+        .iter()
+        .map(|param| format!("'{}", param.to_string().to_snake_case()))
+        .collect::<Vec<_>>();
+    let type_variables = match type_variables.as_slice() {
+        [] => String::new(),
+        [variable] => format!("{variable} "),
+        variables => format!("({}) ", variables.join(", ")),
+    };
+    let binding_fmt = format!("{type_variables}{ocaml_type_name_str} = {body}{unboxed}");
+    let recursive_group = &type_rep.recursive_group;
+
+    Some(quote! {
+        fn ocaml_type_binding() -> Option<String> {
+            Some(format!(#binding_fmt #(, #args)*))
+        }
+
+        fn ocaml_type_definition() -> Option<String> {
+            let bindings = [
+                Self::ocaml_type_binding()?,
+                #(<#recursive_group as ocaml_interop::OCamlDescriber>::ocaml_type_binding()?,)*
+            ];
+            Some(format!("type {}", bindings.join(" and ")))
+        }
+    })
+}
+
+/// Whether the description depends on the representation of the type: its fields for tuples and
+/// definitions, or its phantom type parameters
+fn needs_type_rep(derive_input: &DeriveInput, type_attrs: &OCamlAttributes) -> bool {
+    type_attrs.is_definition()
+        || type_attrs.is_tuple()
+        || type_attrs.is_unboxed()
+        || derive_input
+            .generics
+            .type_params()
+            .any(|param| param.attrs.iter().any(|attr| attr.path().is_ident("ocaml")))
+}
+
 pub fn expand_ocaml_describer(input: TokenStream) -> Result<TokenStream> {
     let derive_input = syn::parse2::<DeriveInput>(input)?;
     let type_ident = &derive_input.ident;
@@ -65,14 +274,26 @@ pub fn expand_ocaml_describer(input: TokenStream) -> Result<TokenStream> {
     // Parse #[ocaml(...)] attributes on the type
     let type_attrs = OCamlAttributes::from_attrs(&derive_input.attrs)?;
 
+    // Without representation attributes only the type name is described, as it doesn't
+    // depend on the fields
+    let type_rep = if needs_type_rep(&derive_input, &type_attrs) {
+        let type_rep = parsing::parse_input(derive_input.clone())?;
+        validation::validate_type_rep(&type_rep)?;
+        Some(type_rep)
+    } else {
+        None
+    };
+
     // Get OCaml type name from attribute or convert from Rust name
     let ocaml_type_name_str = type_attrs
         .get_name()
         .clone()
         .unwrap_or_else(|| type_ident.to_string().to_snake_case());
 
-    // Handle generics
-    let generics = &derive_input.generics;
+    // Handle generics, without the `#[ocaml(phantom)]` attributes of the type parameters
+    let generics = type_rep
+        .as_ref()
+        .map_or(&derive_input.generics, |type_rep| &type_rep.generics);
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     // Add OCamlDescriber bound to generic type parameters
@@ -84,13 +305,26 @@ pub fn expand_ocaml_describer(input: TokenStream) -> Result<TokenStream> {
     );
 
     // Generate the implementation
-    let final_ocaml_name_code = generate_type_name_code(&ocaml_type_name_str, generics);
+    let final_ocaml_name_code = match type_rep.as_ref().map(|type_rep| &type_rep.data) {
+        Some(TypeRepData::Struct { fields }) if type_attrs.is_tuple() => {
+            generate_tuple_type_name_code(fields)
+        }
+        _ => generate_type_name_code(&ocaml_type_name_str, generics),
+    };
+    // The definition describes the fields with their `OCamlDescriber` implementations, so it is
+    // only generated when requested with `#[ocaml(definition)]`
+    let type_definition_code = type_rep
+        .as_ref()
+        .filter(|_| type_attrs.is_definition())
+        .and_then(|type_rep| generate_type_definition_code(type_rep, &ocaml_type_name_str));
 
     let expanded = quote! {
         impl #impl_generics ocaml_interop::OCamlDescriber for #type_ident #ty_generics #where_clause {
             fn ocaml_type_name() -> String {
                 #final_ocaml_name_code
             }
+
+            #type_definition_code
         }
     };
 
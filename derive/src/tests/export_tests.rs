@@ -888,3 +888,77 @@ fn test_bytecode_all_primitives_args_and_return() {
 
     assert_eq!(actual_expansion.to_string(), expected_expansion.to_string());
 }
+
+#[test]
+fn test_callback_arg() {
+    let attributes = quote! {};
+    let input_function = quote! {
+        pub fn call_with_two(cr: &mut OCamlRuntime, f: OCamlCallback<(OCamlInt,), String>) -> OCaml<String> {
+            f.call(cr, 2i64).unwrap()
+        }
+    };
+
+    let expected_expansion = quote! {
+        #[no_mangle]
+        pub extern "C" fn call_with_two(f: ::ocaml_interop::RawOCaml) -> ::ocaml_interop::RawOCaml {
+            let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+                let cr : &mut OCamlRuntime = unsafe { ::ocaml_interop::internal::recover_runtime_handle_mut() };
+                let f: OCamlCallback<(OCamlInt,), String> = unsafe {
+                    <OCamlCallback<(OCamlInt,), String> >::from_raw(f)
+                };
+                let result_from_body: OCaml<String> = {
+                    f.call(cr, 2i64).unwrap()
+                };
+                unsafe { result_from_body.raw() }
+            }));
+            match result {
+                Ok(value) => value,
+                Err(panic_payload) => {
+                    unsafe {
+                        ::ocaml_interop::internal::process_panic_payload_and_raise_ocaml_exception(panic_payload);
+                        ::std::unreachable!(
+                            "process_panic_payload_and_raise_ocaml_exception should not return"
+                        );
+                    }
+                }
+            }
+        }
+    };
+
+    let actual_expansion_result = export_internal_logic(attributes, input_function);
+    assert!(
+        actual_expansion_result.is_ok(),
+        "Macro expansion failed: {:?}",
+        actual_expansion_result.err().map(|e| e.to_string())
+    );
+    let actual_expansion = actual_expansion_result.unwrap();
+
+    assert_eq!(actual_expansion.to_string(), expected_expansion.to_string());
+}
+
+#[test]
+fn test_error_noalloc_with_callback_arg() {
+    let attributes = quote! { noalloc };
+    let input_function = quote! {
+        pub fn call_noalloc(cr: &OCamlRuntime, f: OCamlCallback<(OCamlInt,), ()>) {
+        }
+    };
+
+    let actual_expansion_result = export_internal_logic(attributes, input_function);
+
+    assert!(
+        actual_expansion_result.is_err(),
+        "Macro expansion should have failed for a noalloc function with a callback argument."
+    );
+
+    if let Err(e) = actual_expansion_result {
+        let error_message = e.to_string();
+        assert!(
+            error_message.contains(
+                "`OCamlCallback<Args, Ret>` arguments are not allowed when `noalloc` is used"
+            ),
+            "Error message did not contain the expected text. Got: {}",
+            error_message
+        );
+    }
+}
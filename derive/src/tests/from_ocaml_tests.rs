@@ -151,16 +151,14 @@ mod tests {
             }
         };
 
+        // Records with only float fields are stored as flat arrays of unboxed floats
         let expected = quote! {
             unsafe impl :: ocaml_interop :: FromOCaml < Point > for Point {
                 fn from_ocaml(v: :: ocaml_interop :: OCaml < Point >) -> Self {
-                    unsafe {
-                        let rust_field_0 = v.field::< <f64 as :: ocaml_interop :: DefaultOCamlMapping>::OCamlType >(0).to_rust();
-                        let rust_field_1 = v.field::< <f64 as :: ocaml_interop :: DefaultOCamlMapping>::OCamlType >(1).to_rust();
-                        Self {
-                            x: rust_field_0,
-                            y: rust_field_1
-                        }
+                    let raw = unsafe { v.raw() };
+                    Self {
+                        x: unsafe { ::ocaml_interop::internal::double_field(raw, 0usize) },
+                        y: unsafe { ::ocaml_interop::internal::double_field(raw, 1usize) }
                     }
                 }
             }
@@ -671,4 +669,425 @@ mod tests {
         let actual = expand_from_ocaml(input).unwrap();
         assert_eq!(actual.to_string(), expected.to_string());
     }
+
+    #[test]
+    fn test_unboxed_record_full_expansion() {
+        let input = parse_quote! {
+            #[ocaml(unboxed)]
+            struct Meters {
+                value: f64,
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Meters> for Meters {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Meters>) -> Self {
+                    let unboxed = unsafe { v.cast::< <f64 as ::ocaml_interop::DefaultOCamlMapping>::OCamlType >() }.to_rust();
+                    Self { value: unboxed }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_unboxed_enum_full_expansion() {
+        let input = parse_quote! {
+            #[ocaml(unboxed)]
+            enum UserId {
+                UserId(#[ocaml(as_ = "OCamlInt")] i64),
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<UserId> for UserId {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<UserId>) -> Self {
+                    let unboxed = unsafe { v.cast::<OCamlInt>() }.to_rust();
+                    Self::UserId(unboxed)
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_struct_with_skip_and_with_fields() {
+        let input = parse_quote! {
+            struct Session {
+                #[ocaml(with = "timestamp", as_ = "OCamlFloat")]
+                started: SystemTime,
+                #[ocaml(skip)]
+                cache: HashMap<String, String>,
+                #[ocaml(skip, default = "default_retries")]
+                retries: u32,
+                user: String,
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Session> for Session {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Session>) -> Self {
+                    unsafe {
+                        let rust_field_0 = timestamp::from_ocaml(v.field::<OCamlFloat>(0));
+                        let rust_field_1 = ::core::default::Default::default();
+                        let rust_field_2 = default_retries();
+                        let rust_field_3 = v.field::< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType >(1).to_rust();
+                        Self {
+                            started: rust_field_0,
+                            cache: rust_field_1,
+                            retries: rust_field_2,
+                            user: rust_field_3
+                        }
+                    }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_enum_with_skipped_variant_field() {
+        let input = parse_quote! {
+            enum Shape {
+                Circle { #[ocaml(skip)] area: Cache, radius: f64 },
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Shape> for Shape {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Shape>) -> Self {
+                    if v.is_long() {
+                        let value = unsafe { ::ocaml_interop::internal::int_val(v.raw()) };
+                        match value {
+                            tag => panic!("Unknown unit variant value: {}", tag),
+                        }
+                    } else {
+                        match v.tag_value() {
+                            0 => {
+                                let area = ::core::default::Default::default();
+                                let radius = unsafe { v.field::< <f64 as ::ocaml_interop::DefaultOCamlMapping>::OCamlType >(0).to_rust() };
+                                Self::Circle { area, radius }
+                            }
+                            tag => panic!("Unknown block variant tag: {}", tag),
+                        }
+                    }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_enum_with_boxed_records() {
+        let input = parse_quote! {
+            enum Shape {
+                #[ocaml(boxed_record)]
+                Label { text: String },
+                #[ocaml(boxed_record)]
+                Circle { radius: f64 },
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Shape> for Shape {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Shape>) -> Self {
+                    if v.is_long() {
+                        let value = unsafe { ::ocaml_interop::internal::int_val(v.raw()) };
+                        match value {
+                            tag => panic!("Unknown unit variant value: {}", tag),
+                        }
+                    } else {
+                        match v.tag_value() {
+                            0 => {
+                                let record = unsafe { v.field::<()>(0) };
+                                let text = unsafe { record.field::< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType >(0).to_rust() };
+                                Self::Label { text }
+                            }
+                            1 => {
+                                let record = unsafe { v.field::<()>(0).raw() };
+                                let radius = unsafe { ::ocaml_interop::internal::double_field(record, 0usize) };
+                                Self::Circle { radius }
+                            }
+                            tag => panic!("Unknown block variant tag: {}", tag),
+                        }
+                    }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_recursive_enum() {
+        let input = quote! {
+            enum Tree {
+                Leaf,
+                Node(Box<Tree>, #[ocaml(as_ = "OCamlInt")] i64, Box<Tree>),
+            }
+        };
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Tree> for Tree {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Tree>) -> Self {
+                    let mut stack = vec![(v, false)];
+                    let mut converted: Vec<Self> = Vec::new();
+                    while let Some((v, children_converted)) = stack.pop() {
+                        if children_converted {
+                            let value = {
+                                if v.is_long() {
+                                    let value = unsafe { ::ocaml_interop::internal::int_val(v.raw()) };
+                                    match value {
+                                        0 => Self::Leaf,
+                                        tag => panic!("Unknown unit variant value: {}", tag),
+                                    }
+                                } else {
+                                    match v.tag_value() {
+                                        0 => {
+                                            let field0 = ::std::boxed::Box::new(converted.pop().expect("recursive field should have been converted first"));
+                                            let field1 = unsafe { v.field::<OCamlInt>(1).to_rust() };
+                                            let field2 = ::std::boxed::Box::new(converted.pop().expect("recursive field should have been converted first"));
+                                            Self::Node(field0, field1, field2)
+                                        }
+                                        tag => panic!("Unknown block variant tag: {}", tag),
+                                    }
+                                }
+                            };
+                            converted.push(value);
+                        } else {
+                            stack.push((v, true));
+                            if v.is_block() {
+                                match v.tag_value() {
+                                    0 => {
+                                        stack.push((unsafe { v.field::<Tree>(0usize) }, false));
+                                        stack.push((unsafe { v.field::<Tree>(2usize) }, false));
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                    converted.pop().expect("the converted value should be the last one left")
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_recursive_struct_with_optional_field() {
+        let input = quote! {
+            struct Chain {
+                #[ocaml(as_ = "OCamlInt")]
+                value: i64,
+                next: Option<Box<Chain>>,
+            }
+        };
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Chain> for Chain {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Chain>) -> Self {
+                    let mut stack = vec![(v, false)];
+                    let mut converted: Vec<Self> = Vec::new();
+                    while let Some((v, children_converted)) = stack.pop() {
+                        if children_converted {
+                            let value = {
+                                unsafe {
+                                    let rust_field_0 = v.field::<OCamlInt>(0).to_rust();
+                                    let rust_field_1 = v.field::< <Option<Box<Chain> > as ::ocaml_interop::DefaultOCamlMapping>::OCamlType>(1)
+                                        .to_option()
+                                        .map(|_| ::std::boxed::Box::new(converted.pop().expect("recursive field should have been converted first")));
+                                    Self { value: rust_field_0, next: rust_field_1 }
+                                }
+                            };
+                            converted.push(value);
+                        } else {
+                            stack.push((v, true));
+                            if let Some(child) = unsafe { v.field::<Option<Chain>>(1usize) }.to_option() {
+                                stack.push((child, false));
+                            }
+                        }
+                    }
+                    converted.pop().expect("the converted value should be the last one left")
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_recursive_struct_with_list_field() {
+        let input = quote! {
+            struct Rose {
+                #[ocaml(as_ = "OCamlInt")]
+                label: i64,
+                #[ocaml(as_ = "OCamlList<Rose>")]
+                children: Vec<Rose>,
+            }
+        };
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<Rose> for Rose {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Rose>) -> Self {
+                    let mut stack = vec![(v, false)];
+                    let mut converted: Vec<Self> = Vec::new();
+                    while let Some((v, children_converted)) = stack.pop() {
+                        if children_converted {
+                            let value = {
+                                unsafe {
+                                    let rust_field_0 = v.field::<OCamlInt>(0).to_rust();
+                                    let rust_field_1 = {
+                                        let mut list = v.field::<OCamlList<Rose> >(1);
+                                        let mut children = ::std::vec::Vec::new();
+                                        while let Some((_, tail)) = list.uncons() {
+                                            children.push(converted.pop().expect("recursive field should have been converted first"));
+                                            list = tail;
+                                        }
+                                        children
+                                    };
+                                    Self { label: rust_field_0, children: rust_field_1 }
+                                }
+                            };
+                            converted.push(value);
+                        } else {
+                            stack.push((v, true));
+                            let mut list = unsafe { v.field::< ::ocaml_interop::OCamlList<Rose>>(1usize) };
+                            while let Some((child, tail)) = list.uncons() {
+                                stack.push((child, false));
+                                list = tail;
+                            }
+                        }
+                    }
+                    converted.pop().expect("the converted value should be the last one left")
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_struct_with_phantom_type_param() {
+        let input = parse_quote! {
+            struct Handle<#[ocaml(phantom)] Cap> {
+                #[ocaml(as_ = "OCamlInt")]
+                id: i64,
+                #[ocaml(phantom)]
+                capability: PhantomData<Cap>,
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl<Cap> ::ocaml_interop::FromOCaml<Handle<Cap> > for Handle<Cap> {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<Handle<Cap> >) -> Self {
+                    unsafe {
+                        let rust_field_0 = v.field::<OCamlInt>(0).to_rust();
+                        let rust_field_1 = ::core::marker::PhantomData;
+                        Self {
+                            id: rust_field_0,
+                            capability: rust_field_1
+                        }
+                    }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_tuple_attribute() {
+        let input = parse_quote! {
+            #[ocaml(tuple)]
+            struct Entry(#[ocaml(as_ = "OCamlInt")] i64, String);
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<(OCamlInt, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType)> for Entry {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<(OCamlInt, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType)>) -> Self {
+                    unsafe {
+                        let rust_field_0 = v.field::<OCamlInt>(0).to_rust();
+                        let rust_field_1 = v.field::< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType >(1).to_rust();
+                        Self(rust_field_0, rust_field_1)
+                    }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_extensible_enum() {
+        let input = parse_quote! {
+            #[ocaml(extensible = "plugin_event")]
+            enum PluginEvent {
+                Started(String),
+                Stopped,
+                Progress {
+                    #[ocaml(as_ = "OCamlInt")]
+                    current: i64,
+                },
+            }
+        };
+
+        let expected = quote! {
+            unsafe impl ::ocaml_interop::FromOCaml<PluginEvent> for PluginEvent {
+                fn from_ocaml(v: ::ocaml_interop::OCaml<PluginEvent>) -> Self {
+                    let constructor = unsafe { ::ocaml_interop::internal::extension_constructor_of(v.raw()) };
+                    if constructor == unsafe { { static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor = ::ocaml_interop::internal::ExtensionConstructor::new("plugin_event.Started"); CONSTRUCTOR.get() } } {
+                        let field0 = unsafe { v.field::< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType >(1).to_rust() };
+                        Self::Started(field0)
+                    } else if constructor == unsafe { { static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor = ::ocaml_interop::internal::ExtensionConstructor::new("plugin_event.Stopped"); CONSTRUCTOR.get() } } {
+                        Self::Stopped
+                    } else if constructor == unsafe { { static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor = ::ocaml_interop::internal::ExtensionConstructor::new("plugin_event.Progress"); CONSTRUCTOR.get() } } {
+                        let current = unsafe { v.field::<OCamlInt>(1).to_rust() };
+                        Self::Progress { current }
+                    } else {
+                        panic!("Unknown extension constructor for PluginEvent")
+                    }
+                }
+            }
+        };
+
+        let actual = expand_from_ocaml(input).unwrap();
+        assert_eq!(actual.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_extensible_enum_rejects_boxed_records() {
+        // The first field of extensible constructor blocks is the extension constructor, and
+        // ToOCaml stores the fields of extensible variants inline
+        let input = parse_quote! {
+            #[ocaml(extensible = "plugin_event")]
+            enum PluginEvent {
+                #[ocaml(boxed_record)]
+                Progress {
+                    #[ocaml(as_ = "OCamlInt")]
+                    current: i64,
+                },
+            }
+        };
+
+        let err_msg = expand_from_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(
+                "#[ocaml(boxed_record)] is only applicable on variants with named fields of regular enums"
+            ),
+            "unexpected error message: {err_msg}"
+        );
+    }
 }
@@ -0,0 +1,182 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use crate::ocaml_accessors::codegen::expand_ocaml_accessors;
+use pretty_assertions::assert_eq;
+use quote::quote;
+
+#[test]
+fn test_accessors_for_record() {
+    let input_struct = quote! {
+        pub struct Person {
+            name: String,
+            #[ocaml(skip)]
+            cache: Vec<u8>,
+            #[ocaml(as_ = "OCamlInt")]
+            age: i64,
+        }
+    };
+    let expected = quote! {
+        #[doc = "Accessors for the fields of OCaml `Person` values, generated by `#[derive(OCamlAccessors)]`."]
+        pub trait PersonAccessors<'a> {
+            #[doc = "Returns the `name` field of the value without converting it."]
+            fn name(&self) -> ::ocaml_interop::OCaml<'a, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType>;
+            #[doc = "Returns the `age` field of the value without converting it."]
+            fn age(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt>;
+        }
+
+        impl<'a> PersonAccessors<'a> for ::ocaml_interop::OCaml<'a, Person> {
+            fn name(&self) -> ::ocaml_interop::OCaml<'a, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> {
+                unsafe { self.field(0usize) }
+            }
+            fn age(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt> {
+                unsafe { self.field(1usize) }
+            }
+        }
+    };
+
+    let actual = expand_ocaml_accessors(input_struct).unwrap();
+    assert_eq!(actual.to_string(), expected.to_string());
+}
+
+#[test]
+fn test_accessors_for_tuple_struct_with_marker() {
+    let input_struct = quote! {
+        #[ocaml(as_ = "OCamlPair")]
+        struct Pair(#[ocaml(as_ = "OCamlInt")] i64, String);
+    };
+    let expected = quote! {
+        #[doc = "Accessors for the fields of OCaml `Pair` values, generated by `#[derive(OCamlAccessors)]`."]
+        trait PairAccessors<'a> {
+            #[doc = "Returns field 0 of the value without converting it."]
+            fn field_0(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt>;
+            #[doc = "Returns field 1 of the value without converting it."]
+            fn field_1(&self) -> ::ocaml_interop::OCaml<'a, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType>;
+        }
+
+        impl<'a> PairAccessors<'a> for ::ocaml_interop::OCaml<'a, OCamlPair> {
+            fn field_0(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt> {
+                unsafe { self.field(0usize) }
+            }
+            fn field_1(&self) -> ::ocaml_interop::OCaml<'a, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> {
+                unsafe { self.field(1usize) }
+            }
+        }
+    };
+
+    let actual = expand_ocaml_accessors(input_struct).unwrap();
+    assert_eq!(actual.to_string(), expected.to_string());
+}
+
+#[test]
+fn test_accessors_for_float_record() {
+    let input_struct = quote! {
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+    };
+    let expected = quote! {
+        #[doc = "Accessors for the fields of OCaml `Point` values, generated by `#[derive(OCamlAccessors)]`."]
+        trait PointAccessors<'a> {
+            #[doc = "Returns the `x` field of the value without converting it."]
+            fn x(&self) -> f64;
+            #[doc = "Returns the `y` field of the value without converting it."]
+            fn y(&self) -> f64;
+        }
+
+        impl<'a> PointAccessors<'a> for ::ocaml_interop::OCaml<'a, Point> {
+            fn x(&self) -> f64 {
+                unsafe { ::ocaml_interop::internal::double_field(self.raw(), 0usize) }
+            }
+            fn y(&self) -> f64 {
+                unsafe { ::ocaml_interop::internal::double_field(self.raw(), 1usize) }
+            }
+        }
+    };
+
+    let actual = expand_ocaml_accessors(input_struct).unwrap();
+    assert_eq!(actual.to_string(), expected.to_string());
+}
+
+#[test]
+fn test_accessors_for_unboxed_struct() {
+    let input_struct = quote! {
+        #[ocaml(unboxed)]
+        struct UserId(#[ocaml(as_ = "OCamlInt")] i64);
+    };
+    let expected = quote! {
+        #[doc = "Accessors for the fields of OCaml `UserId` values, generated by `#[derive(OCamlAccessors)]`."]
+        trait UserIdAccessors<'a> {
+            #[doc = "Returns field 0 of the value without converting it."]
+            fn field_0(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt>;
+        }
+
+        impl<'a> UserIdAccessors<'a> for ::ocaml_interop::OCaml<'a, UserId> {
+            fn field_0(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt> {
+                unsafe { self.cast() }
+            }
+        }
+    };
+
+    let actual = expand_ocaml_accessors(input_struct).unwrap();
+    assert_eq!(actual.to_string(), expected.to_string());
+}
+
+#[test]
+fn test_accessors_rejected_for_unsupported_types() {
+    let enum_input = quote! {
+        enum Status {
+            Ok,
+            Error(String),
+        }
+    };
+    let generic_input = quote! {
+        struct Wrapper<T> {
+            value: T,
+        }
+    };
+    let skipped_input = quote! {
+        struct Empty {
+            #[ocaml(skip)]
+            value: i64,
+        }
+    };
+
+    for input in [enum_input, generic_input, skipped_input] {
+        let err = expand_ocaml_accessors(input).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("#[derive(OCamlAccessors)] is not supported"),
+            "unexpected error: {err}"
+        );
+    }
+}
+
+#[test]
+fn test_accessors_for_struct_with_phantom_type_param() {
+    let input_struct = quote! {
+        struct Handle<#[ocaml(phantom)] Cap> {
+            #[ocaml(as_ = "OCamlInt")]
+            id: i64,
+            #[ocaml(phantom)]
+            capability: PhantomData<Cap>,
+        }
+    };
+    let expected = quote! {
+        #[doc = "Accessors for the fields of OCaml `Handle` values, generated by `#[derive(OCamlAccessors)]`."]
+        trait HandleAccessors<'a> {
+            #[doc = "Returns the `id` field of the value without converting it."]
+            fn id(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt>;
+        }
+
+        impl<'a, Cap> HandleAccessors<'a> for ::ocaml_interop::OCaml<'a, Handle<Cap> > {
+            fn id(&self) -> ::ocaml_interop::OCaml<'a, OCamlInt> {
+                unsafe { self.field(0usize) }
+            }
+        }
+    };
+
+    let actual = expand_ocaml_accessors(input_struct).unwrap();
+    assert_eq!(actual.to_string(), expected.to_string());
+}
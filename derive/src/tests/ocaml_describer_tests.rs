@@ -169,3 +169,340 @@ fn test_ocaml_describer_lifetime_and_type_param() {
 
     assert_eq!(actual_impl.to_string(), expected_impl.to_string());
 }
+
+#[test]
+fn test_ocaml_describer_unboxed() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition, unboxed)]
+        struct UserId(i64);
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for UserId {
+            fn ocaml_type_name() -> String {
+                "user_id".to_string()
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "user_id = UserId of {} [@@unboxed]",
+                    < <i64 as ::ocaml_interop::DefaultOCamlMapping>::OCamlType as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [Self::ocaml_type_binding()?,];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+
+    let err_msg = expand_ocaml_describer(quote! {
+        #[ocaml(unboxed)]
+        struct Point(i64, i64);
+    })
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err_msg.contains("#[ocaml(unboxed)] is only applicable on structs with a single field"),
+        "unexpected error message: {err_msg}"
+    );
+}
+
+#[test]
+fn test_ocaml_describer_name_only_without_definition() {
+    // Fields without OCamlDescriber or DefaultOCamlMapping implementations are not described
+    let actual_impl = expand_ocaml_describer(quote! {
+        struct Session {
+            pair: (i64, String),
+            handle: Opaque,
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Session {
+            fn ocaml_type_name() -> String {
+                "session".to_string()
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_ocaml_describer_record_definition() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition)]
+        struct Session {
+            #[ocaml(rename = "user_name")]
+            user: String,
+            #[ocaml(skip)]
+            cache: Cache,
+            #[ocaml(mutable, as_ = "OCamlInt")]
+            count: i64,
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Session {
+            fn ocaml_type_name() -> String {
+                "session".to_string()
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "session = {{ user_name : {}; mutable count : {} }}",
+                    < <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType as ocaml_interop::OCamlDescriber>::ocaml_type_name(),
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [Self::ocaml_type_binding()?,];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_ocaml_describer_variant_definitions() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition)]
+        enum Movement {
+            Step(#[ocaml(as_ = "OCamlInt")] i64),
+            #[ocaml(rename = "Resize")]
+            Expand {
+                #[ocaml(as_ = "OCamlInt")]
+                width: i64,
+            },
+            RotateLeft,
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Movement {
+            fn ocaml_type_name() -> String {
+                "movement".to_string()
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "movement = Step of {} | Resize of {{ width : {} }} | RotateLeft",
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name(),
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [Self::ocaml_type_binding()?,];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition, polymorphic_variant)]
+        enum Action {
+            #[ocaml(tag = "set_speed")]
+            SetSpeed(#[ocaml(as_ = "OCamlInt")] i64),
+            Stop,
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Action {
+            fn ocaml_type_name() -> String {
+                "action".to_string()
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "action = [ `set_speed of {} | `Stop ]",
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [Self::ocaml_type_binding()?,];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_ocaml_describer_boxed_record_definition() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition)]
+        enum Shape {
+            Rect {
+                #[ocaml(as_ = "OCamlInt")]
+                width: i64,
+            },
+            #[ocaml(boxed_record)]
+            RoundedRect {
+                #[ocaml(as_ = "OCamlInt")]
+                radius: i64,
+            },
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Shape {
+            fn ocaml_type_name() -> String {
+                "shape".to_string()
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "shape = Rect of {{ width : {} }} | RoundedRect of shape_rounded_rect and shape_rounded_rect = {{ radius : {} }}",
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name(),
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [Self::ocaml_type_binding()?,];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_ocaml_describer_mutually_recursive_group() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition, and = "Stmt")]
+        enum Expr {
+            Int(#[ocaml(as_ = "OCamlInt")] i64),
+            Block(#[ocaml(as_ = "OCamlList<Stmt>")] Vec<Stmt>),
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Expr {
+            fn ocaml_type_name() -> String {
+                "expr".to_string()
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "expr = Int of {} | Block of {}",
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name(),
+                    <OCamlList<Stmt> as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [
+                    Self::ocaml_type_binding()?,
+                    <Stmt as ocaml_interop::OCamlDescriber>::ocaml_type_binding()?,
+                ];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+
+    let err_msg = expand_ocaml_describer(quote! {
+        #[ocaml(definition)]
+        struct Stmt {
+            #[ocaml(and = "Expr")]
+            expr: Expr,
+        }
+    })
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err_msg.contains("#[ocaml(and)] is only applicable on structs and enums"),
+        "unexpected error message: {err_msg}"
+    );
+}
+
+#[test]
+fn test_ocaml_describer_phantom_type_param() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition)]
+        struct Handle<#[ocaml(phantom)] Cap> {
+            #[ocaml(as_ = "OCamlInt")]
+            id: i64,
+            #[ocaml(phantom)]
+            capability: PhantomData<Cap>,
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl<Cap> ocaml_interop::OCamlDescriber for Handle<Cap>
+        where
+            Cap: ocaml_interop::OCamlDescriber
+        {
+            fn ocaml_type_name() -> String {
+                format!(
+                    "{} {}",
+                    <Cap as ocaml_interop::OCamlDescriber>::ocaml_type_name(),
+                    "handle"
+                )
+            }
+
+            fn ocaml_type_binding() -> Option<String> {
+                Some(format!(
+                    "'cap handle = {{ id : {} }}",
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                ))
+            }
+
+            fn ocaml_type_definition() -> Option<String> {
+                let bindings = [Self::ocaml_type_binding()?,];
+                Some(format!("type {}", bindings.join(" and ")))
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_ocaml_describer_tuple() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(tuple)]
+        struct Entry(#[ocaml(as_ = "OCamlInt")] i64, String);
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for Entry {
+            fn ocaml_type_name() -> String {
+                format!(
+                    "({} * {})",
+                    <OCamlInt as ocaml_interop::OCamlDescriber>::ocaml_type_name(),
+                    < <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType as ocaml_interop::OCamlDescriber>::ocaml_type_name()
+                )
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_ocaml_describer_extensible_enum_has_no_definition() {
+    let actual_impl = expand_ocaml_describer(quote! {
+        #[ocaml(definition, extensible = "plugin_event")]
+        enum PluginEvent {
+            Started(String),
+            Stopped,
+        }
+    })
+    .unwrap();
+    let expected_impl = quote! {
+        impl ocaml_interop::OCamlDescriber for PluginEvent {
+            fn ocaml_type_name() -> String {
+                "plugin_event".to_string()
+            }
+        }
+    };
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
@@ -437,3 +437,940 @@ fn test_to_ocaml_polymorphic_enum_with_payloads() {
 
     assert_eq!(actual_impl.to_string(), expected_impl.to_string());
 }
+
+#[test]
+fn test_to_ocaml_float_record() {
+    let input_struct = quote! {
+        #[ocaml(float_record)]
+        struct Point {
+            x: f64,
+            #[ocaml(as_ = "OCamlFloat")]
+            y: f64,
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Point> for Point {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Point> {
+                unsafe {
+                    let record = ::ocaml_interop::internal::alloc_float_record(2usize);
+                    ::ocaml_interop::internal::store_double_field(record, 0usize, self.x);
+                    ::ocaml_interop::internal::store_double_field(record, 1usize, self.y);
+                    ::ocaml_interop::OCaml::new(cr, record)
+                }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+
+    // Detected without the attribute for structs with only `f64` fields
+    let detected_impl = expand_to_ocaml(quote! {
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+    })
+    .unwrap();
+    assert_eq!(detected_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_float_record_not_detected() {
+    // Tuple structs are tuples in OCaml, and fields mapped to other types are boxed
+    for input_struct in [
+        quote! { struct Pair(f64, f64); },
+        quote! {
+            struct Rounded {
+                x: f64,
+                #[ocaml(as_ = "OCamlInt")]
+                y: f64,
+            }
+        },
+    ] {
+        let actual_impl = expand_to_ocaml(input_struct).unwrap().to_string();
+        assert!(
+            !actual_impl.contains("alloc_float_record"),
+            "unexpected float record layout: {actual_impl}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_float_record_rejects_non_float_fields() {
+    let input_struct = quote! {
+        #[ocaml(float_record)]
+        struct Labelled {
+            x: f64,
+            label: String,
+        }
+    };
+
+    let err_msg = expand_to_ocaml(input_struct).unwrap_err().to_string();
+    assert!(
+        err_msg.contains("all the fields of a float record must be `f64`"),
+        "unexpected error message: {err_msg}"
+    );
+}
+
+#[test]
+fn test_to_ocaml_unboxed_newtype() {
+    let input_struct = quote! {
+        #[ocaml(unboxed)]
+        struct UserId(#[ocaml(as_ = "OCamlInt")] i64);
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<UserId> for UserId {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, UserId> {
+                let unboxed: ::ocaml_interop::OCaml<OCamlInt> = self.0.to_ocaml(cr);
+                unsafe { unboxed.cast() }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_unboxed_single_constructor_enum() {
+    let input_enum = quote! {
+        #[ocaml(unboxed)]
+        enum Name {
+            Name(String),
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Name> for Name {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Name> {
+                match self {
+                    Name::Name(ref field0) => {
+                        let unboxed: ::ocaml_interop::OCaml< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = field0.to_ocaml(cr);
+                        unsafe { unboxed.cast() }
+                    }
+                }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_unboxed_rejects_multiple_fields() {
+    for input in [
+        quote! {
+            #[ocaml(unboxed)]
+            struct Pair(i64, i64);
+        },
+        quote! {
+            #[ocaml(unboxed)]
+            enum Either {
+                Left(i64),
+                Right(i64),
+            }
+        },
+    ] {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains("#[ocaml(unboxed)] is only applicable on structs with a single field"),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_skip_and_with_fields() {
+    let input_struct = quote! {
+        struct Session {
+            #[ocaml(with = "timestamp", as_ = "OCamlFloat")]
+            started: SystemTime,
+            #[ocaml(skip)]
+            cache: HashMap<String, String>,
+            user: String,
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Session> for Session {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Session> {
+                let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                    ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                });
+
+                let field_started_ocaml: ::ocaml_interop::OCaml<OCamlFloat> = timestamp::to_ocaml(&self.started, cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, field_started_ocaml.raw());
+                }
+
+                let field_user_ocaml: ::ocaml_interop::OCaml< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = self.user.to_ocaml(cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 1usize, field_user_ocaml.raw());
+                }
+
+                unsafe { ::ocaml_interop::OCaml::new(cr, record_root.get_raw()) }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_skipped_variant_field() {
+    let input_enum = quote! {
+        enum Shape {
+            Circle(#[ocaml(skip)] Cache, f64),
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Shape> for Shape {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Shape> {
+                match self {
+                    Shape::Circle(_, ref field1) => {
+                        let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(1usize, 0))
+                        });
+                        let ocaml_field0: ::ocaml_interop::OCaml< <f64 as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = field1.to_ocaml(cr);
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(block_root.get_raw(), 0usize, ocaml_field0.raw());
+                        }
+                        unsafe { ::ocaml_interop::OCaml::new(cr, block_root.get_raw()) }
+                    }
+                }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_field_attribute_errors() {
+    let cases = [
+        (
+            quote! {
+                struct Config {
+                    #[ocaml(default)]
+                    retries: i64,
+                }
+            },
+            "#[ocaml(default)] is only applicable on fields marked with #[ocaml(skip)]",
+        ),
+        (
+            quote! {
+                struct Config {
+                    #[ocaml(skip, with = "retries")]
+                    retries: i64,
+                }
+            },
+            "can't be used on a field marked with #[ocaml(skip)]",
+        ),
+        (
+            quote! {
+                #[ocaml(polymorphic_variant)]
+                enum Event {
+                    Click(#[ocaml(skip)] Cache, i64),
+                }
+            },
+            "#[ocaml(skip)] is only applicable on fields of structs and of regular enum variants",
+        ),
+        (
+            quote! {
+                enum Event {
+                    Click(#[ocaml(skip)] Cache),
+                }
+            },
+            "variant 'Click' must keep at least one field",
+        ),
+    ];
+    for (input, expected_message) in cases {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(expected_message),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_inline_and_boxed_records() {
+    let input_enum = quote! {
+        enum Shape {
+            #[ocaml(inline_record)]
+            Rect { #[ocaml(as_ = "OCamlInt")] width: i64 },
+            #[ocaml(boxed_record)]
+            Label { text: String },
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Shape> for Shape {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Shape> {
+                match self {
+                    Shape::Rect { width: ref width } => {
+                        let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(1usize, 0))
+                        });
+                        let ocaml_field0: ::ocaml_interop::OCaml<OCamlInt> = width.to_ocaml(cr);
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(block_root.get_raw(), 0usize, ocaml_field0.raw());
+                        }
+                        unsafe { ::ocaml_interop::OCaml::new(cr, block_root.get_raw()) }
+                    }
+                    Shape::Label { text: ref text } => {
+                        let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(1usize, 0))
+                        });
+                        let ocaml_field0: ::ocaml_interop::OCaml< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = text.to_ocaml(cr);
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, ocaml_field0.raw());
+                        }
+                        let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(1usize, 1))
+                        });
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(block_root.get_raw(), 0usize, record_root.get_raw());
+                            ::ocaml_interop::OCaml::new(cr, block_root.get_raw())
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_boxed_float_record() {
+    let input_enum = quote! {
+        enum Shape {
+            #[ocaml(boxed_record)]
+            Circle { radius: f64 },
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Shape> for Shape {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Shape> {
+                match self {
+                    Shape::Circle { radius: ref radius } => {
+                        let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(1usize, 0))
+                        });
+                        unsafe {
+                            let record = ::ocaml_interop::internal::alloc_float_record(1usize);
+                            ::ocaml_interop::internal::store_double_field(record, 0usize, *radius);
+                            ::ocaml_interop::internal::store_field(block_root.get_raw(), 0usize, record);
+                            ::ocaml_interop::OCaml::new(cr, block_root.get_raw())
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_record_kind_errors() {
+    let cases = [
+        (
+            quote! {
+                enum Shape {
+                    #[ocaml(boxed_record)]
+                    Circle(f64),
+                }
+            },
+            "#[ocaml(boxed_record)] is only applicable on variants with named fields of regular enums",
+        ),
+        (
+            quote! {
+                enum Shape {
+                    #[ocaml(inline_record, boxed_record)]
+                    Circle { radius: f64 },
+                }
+            },
+            "can't be used together",
+        ),
+    ];
+    for (input, expected_message) in cases {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(expected_message),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_mutable_field_setters() {
+    let input_struct = quote! {
+        pub struct Counter {
+            name: String,
+            #[ocaml(mutable, as_ = "OCamlInt")]
+            count: i64,
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Counter> for Counter {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Counter> {
+                let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                    ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                });
+
+                let field_name_ocaml: ::ocaml_interop::OCaml< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = self.name.to_ocaml(cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, field_name_ocaml.raw());
+                }
+
+                let field_count_ocaml: ::ocaml_interop::OCaml<OCamlInt> = self.count.to_ocaml(cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 1usize, field_count_ocaml.raw());
+                }
+
+                unsafe { ::ocaml_interop::OCaml::new(cr, record_root.get_raw()) }
+            }
+        }
+
+        #[doc = "Setters for the mutable fields of rooted OCaml `Counter` records, generated by `#[derive(ToOCaml)]`.\n\nImplemented for `OCamlRef`, and so usable on `BoxRoot` values. Setting a field needs `&mut OCamlRuntime`, which can't be used while an `OCaml` value borrowed from it is alive, and converting non-float fields allocates, which could move an unrooted record."]
+        pub trait CounterSetters {
+            #[doc = "Sets the `count` field of the record."]
+            fn set_count(self, cr: &mut ::ocaml_interop::OCamlRuntime, value: i64);
+        }
+
+        impl<'r> CounterSetters for ::ocaml_interop::OCamlRef<'r, Counter> {
+            fn set_count(self, cr: &mut ::ocaml_interop::OCamlRuntime, value: i64) {
+                let ocaml_value: ::ocaml_interop::OCaml<OCamlInt> = value.to_ocaml(cr);
+                unsafe { ::ocaml_interop::internal::store_field(self.get_raw(), 1usize, ocaml_value.raw()) }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_mutable_float_record_setters() {
+    let input_struct = quote! {
+        struct Position {
+            x: f64,
+            #[ocaml(mutable)]
+            y: f64,
+        }
+    };
+    let expected_setters = quote! {
+        #[doc = "Setters for the mutable fields of rooted OCaml `Position` records, generated by `#[derive(ToOCaml)]`.\n\nImplemented for `OCamlRef`, and so usable on `BoxRoot` values. Setting a field needs `&mut OCamlRuntime`, which can't be used while an `OCaml` value borrowed from it is alive, and converting non-float fields allocates, which could move an unrooted record."]
+        trait PositionSetters {
+            #[doc = "Sets the `y` field of the record."]
+            fn set_y(self, cr: &mut ::ocaml_interop::OCamlRuntime, value: f64);
+        }
+
+        impl<'r> PositionSetters for ::ocaml_interop::OCamlRef<'r, Position> {
+            fn set_y(self, _cr: &mut ::ocaml_interop::OCamlRuntime, value: f64) {
+                unsafe { ::ocaml_interop::internal::store_double_field(self.get_raw(), 1usize, value) }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap().to_string();
+    assert!(
+        actual_impl.ends_with(&expected_setters.to_string()),
+        "unexpected expansion: {actual_impl}"
+    );
+}
+
+#[test]
+fn test_to_ocaml_mutable_rejected_outside_records() {
+    let cases = [
+        quote! {
+            enum Event {
+                Click { #[ocaml(mutable)] count: i64 },
+            }
+        },
+        quote! {
+            struct Pair(#[ocaml(mutable)] i64, i64);
+        },
+        quote! {
+            #[ocaml(unboxed)]
+            struct Id { #[ocaml(mutable)] id: i64 }
+        },
+    ];
+    for input in cases {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(
+                "#[ocaml(mutable)] is only applicable on named fields of non-generic structs"
+            ),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_recursive_enum() {
+    let input_enum = quote! {
+        enum Tree {
+            Leaf,
+            Node(Box<Tree>, #[ocaml(as_ = "OCamlInt")] i64, Box<Tree>),
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Tree> for Tree {
+            fn to_ocaml<'a>(&self, cr: &'a mut ::ocaml_interop::OCamlRuntime) -> ::ocaml_interop::OCaml<'a, Tree> {
+                let mut stack: Vec<(&Self, bool)> = vec![(self, false)];
+                let mut converted: Vec<::ocaml_interop::BoxRoot<Tree>> = Vec::new();
+                while let Some((node, children_converted)) = stack.pop() {
+                    if children_converted {
+                        let ocaml_node: ::ocaml_interop::OCaml<Tree> = {
+                            match node {
+                                Tree::Leaf => {
+                                    unsafe { ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::make_ocaml_int(0isize)) }
+                                }
+                                Tree::Node(_, ref field1, _) => {
+                                    let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                                        ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(3usize, 0))
+                                    });
+                                    let ocaml_field0: ::ocaml_interop::OCaml< <Box<Tree> as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> =
+                                        converted.pop().expect("recursive field should have been converted first").get(cr);
+                                    unsafe {
+                                        ::ocaml_interop::internal::store_field(block_root.get_raw(), 0usize, ocaml_field0.raw());
+                                    }
+                                    let ocaml_field1: ::ocaml_interop::OCaml<OCamlInt> = field1.to_ocaml(cr);
+                                    unsafe {
+                                        ::ocaml_interop::internal::store_field(block_root.get_raw(), 1usize, ocaml_field1.raw());
+                                    }
+                                    let ocaml_field2: ::ocaml_interop::OCaml< <Box<Tree> as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> =
+                                        converted.pop().expect("recursive field should have been converted first").get(cr);
+                                    unsafe {
+                                        ::ocaml_interop::internal::store_field(block_root.get_raw(), 2usize, ocaml_field2.raw());
+                                    }
+                                    unsafe { ::ocaml_interop::OCaml::new(cr, block_root.get_raw()) }
+                                }
+                            }
+                        };
+                        converted.push(ocaml_node.root());
+                    } else {
+                        stack.push((node, true));
+                        match node {
+                            Tree::Leaf { .. } => {}
+                            Tree::Node { 0: child0, 2: child2, .. } => {
+                                stack.push((&**child0, false));
+                                stack.push((&**child2, false));
+                            }
+                        }
+                    }
+                }
+                converted.pop().expect("the converted value should be the last one left").get(cr)
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_recursive_struct_with_optional_field() {
+    let input_struct = quote! {
+        struct Chain {
+            #[ocaml(as_ = "OCamlInt")]
+            value: i64,
+            next: Option<Box<Self>>,
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Chain> for Chain {
+            fn to_ocaml<'a>(&self, cr: &'a mut ::ocaml_interop::OCamlRuntime) -> ::ocaml_interop::OCaml<'a, Chain> {
+                let mut stack: Vec<(&Self, bool)> = vec![(self, false)];
+                let mut converted: Vec<::ocaml_interop::BoxRoot<Chain>> = Vec::new();
+                while let Some((node, children_converted)) = stack.pop() {
+                    if children_converted {
+                        let ocaml_node: ::ocaml_interop::OCaml<Chain> = {
+                            let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                                ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                            });
+                            let field_value_ocaml: ::ocaml_interop::OCaml<OCamlInt> = node.value.to_ocaml(cr);
+                            unsafe {
+                                ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, field_value_ocaml.raw());
+                            }
+                            let field_next_ocaml: ::ocaml_interop::OCaml< <Option<Box<Self> > as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> =
+                                node.next.as_ref().map(|_| converted.pop().expect("recursive field should have been converted first")).to_ocaml(cr);
+                            unsafe {
+                                ::ocaml_interop::internal::store_field(record_root.get_raw(), 1usize, field_next_ocaml.raw());
+                            }
+                            unsafe { ::ocaml_interop::OCaml::new(cr, record_root.get_raw()) }
+                        };
+                        converted.push(ocaml_node.root());
+                    } else {
+                        stack.push((node, true));
+                        let Chain { next: child1, .. } = node;
+                        if let Some(child) = child1 {
+                            stack.push((&**child, false));
+                        }
+                    }
+                }
+                converted.pop().expect("the converted value should be the last one left").get(cr)
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_recursive_struct_with_list_field() {
+    let input_struct = quote! {
+        struct Rose {
+            #[ocaml(as_ = "OCamlInt")]
+            label: i64,
+            children: Vec<Rose>,
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<Rose> for Rose {
+            fn to_ocaml<'a>(&self, cr: &'a mut ::ocaml_interop::OCamlRuntime) -> ::ocaml_interop::OCaml<'a, Rose> {
+                let mut stack: Vec<(&Self, bool)> = vec![(self, false)];
+                let mut converted: Vec<::ocaml_interop::BoxRoot<Rose>> = Vec::new();
+                while let Some((node, children_converted)) = stack.pop() {
+                    if children_converted {
+                        let ocaml_node: ::ocaml_interop::OCaml<Rose> = {
+                            let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                                ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                            });
+                            let field_label_ocaml: ::ocaml_interop::OCaml<OCamlInt> = node.label.to_ocaml(cr);
+                            unsafe {
+                                ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, field_label_ocaml.raw());
+                            }
+                            let field_children_ocaml: ::ocaml_interop::OCaml< <Vec<Rose> as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> =
+                                node.children
+                                    .iter()
+                                    .map(|_| converted.pop().expect("recursive field should have been converted first"))
+                                    .collect::<::std::vec::Vec<_>>()
+                                    .to_ocaml(cr);
+                            unsafe {
+                                ::ocaml_interop::internal::store_field(record_root.get_raw(), 1usize, field_children_ocaml.raw());
+                            }
+                            unsafe { ::ocaml_interop::OCaml::new(cr, record_root.get_raw()) }
+                        };
+                        converted.push(ocaml_node.root());
+                    } else {
+                        stack.push((node, true));
+                        let Rose { children: child1, .. } = node;
+                        for child in child1 {
+                            stack.push((child, false));
+                        }
+                    }
+                }
+                converted.pop().expect("the converted value should be the last one left").get(cr)
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_recursive_vec_as_array_uses_recursive_calls() {
+    let input_struct = quote! {
+        struct Rose {
+            #[ocaml(as_ = "OCamlInt")]
+            label: i64,
+            #[ocaml(as_ = "OCamlUniformArray<Rose>")]
+            children: Vec<Rose>,
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap().to_string();
+    assert!(!actual_impl.contains("converted"), "{actual_impl}");
+}
+
+#[test]
+fn test_to_ocaml_generic_recursive_type_uses_recursive_calls() {
+    let input_enum = quote! {
+        enum Tree<T> {
+            Leaf(T),
+            Node(Box<Tree<T>>, Box<Tree<T>>),
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap().to_string();
+    assert!(!actual_impl.contains("converted"), "{actual_impl}");
+}
+
+#[test]
+fn test_to_ocaml_phantom_type_param() {
+    let input_struct = quote! {
+        struct Handle<#[ocaml(phantom)] Cap> {
+            #[ocaml(as_ = "OCamlInt")]
+            id: i64,
+            #[ocaml(phantom)]
+            capability: PhantomData<Cap>,
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl<Cap> ::ocaml_interop::ToOCaml<Handle<Cap> > for Handle<Cap> {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, Handle<Cap> > {
+                let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                    ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(1usize, 0))
+                });
+
+                let field_id_ocaml: ::ocaml_interop::OCaml<OCamlInt> = self.id.to_ocaml(cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, field_id_ocaml.raw());
+                }
+
+                unsafe { ::ocaml_interop::OCaml::new(cr, record_root.get_raw()) }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_phantom_errors() {
+    let cases = [
+        (
+            quote! {
+                struct Handle {
+                    #[ocaml(phantom)]
+                    id: i64,
+                }
+            },
+            "#[ocaml(phantom)] is only applicable on PhantomData fields and type parameters",
+        ),
+        (
+            quote! {
+                struct Handle<#[ocaml(phantom)] Cap> {
+                    id: i64,
+                    capability: Cap,
+                }
+            },
+            "phantom type parameter 'Cap' can't be the type of a field",
+        ),
+    ];
+    for (input, expected_message) in cases {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(expected_message),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_tuple_attribute() {
+    let input_struct = quote! {
+        #[ocaml(tuple)]
+        struct Entry(#[ocaml(as_ = "OCamlInt")] i64, String);
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<(OCamlInt, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType)> for Entry {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, (OCamlInt, <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType)> {
+                let record_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                    ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                });
+
+                let field_0_ocaml: ::ocaml_interop::OCaml<OCamlInt> = self.0.to_ocaml(cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 0usize, field_0_ocaml.raw());
+                }
+
+                let field_1_ocaml: ::ocaml_interop::OCaml< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = self.1.to_ocaml(cr);
+                unsafe {
+                    ::ocaml_interop::internal::store_field(record_root.get_raw(), 1usize, field_1_ocaml.raw());
+                }
+
+                unsafe { ::ocaml_interop::OCaml::new(cr, record_root.get_raw()) }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_struct).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_tuple_attribute_errors() {
+    let cases = [
+        (
+            quote! {
+                #[ocaml(tuple)]
+                struct Entry { key: i64, value: String }
+            },
+            "#[ocaml(tuple)] is only applicable on non-generic tuple structs with at least two fields",
+        ),
+        (
+            quote! {
+                #[ocaml(tuple)]
+                struct Entry(i64, #[ocaml(skip)] String);
+            },
+            "#[ocaml(tuple)] is only applicable on non-generic tuple structs with at least two fields",
+        ),
+        (
+            quote! {
+                #[ocaml(tuple)]
+                struct Entry<T>(T, String);
+            },
+            "#[ocaml(tuple)] is only applicable on non-generic tuple structs with at least two fields",
+        ),
+        (
+            quote! {
+                #[ocaml(tuple, as_ = "OCamlEntry")]
+                struct Entry(i64, String);
+            },
+            "#[ocaml(tuple)] can't be combined with #[ocaml(as_ = \"...\")]",
+        ),
+    ];
+    for (input, expected_message) in cases {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(expected_message),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
+
+#[test]
+fn test_to_ocaml_extensible_enum() {
+    let input_enum = quote! {
+        #[ocaml(extensible = "plugin_event")]
+        enum PluginEvent {
+            Started(String),
+            #[ocaml(rename = "Done")]
+            Stopped,
+            Progress {
+                #[ocaml(as_ = "OCamlInt")]
+                current: i64,
+                #[ocaml(skip)]
+                cache: Cache,
+            },
+        }
+    };
+    let expected_impl = quote! {
+        unsafe impl ::ocaml_interop::ToOCaml<PluginEvent> for PluginEvent {
+            fn to_ocaml<'a>(
+                &self,
+                cr: &'a mut ::ocaml_interop::OCamlRuntime
+            ) -> ::ocaml_interop::OCaml<'a, PluginEvent> {
+                match self {
+                    PluginEvent::Started(ref field0) => {
+                        let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                        });
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(
+                                block_root.get_raw(),
+                                0usize,
+                                { static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor = ::ocaml_interop::internal::ExtensionConstructor::new("plugin_event.Started"); CONSTRUCTOR.get() },
+                            );
+                        }
+                        let ocaml_field0: ::ocaml_interop::OCaml< <String as ::ocaml_interop::DefaultOCamlMapping>::OCamlType> = field0.to_ocaml(cr);
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(block_root.get_raw(), 1usize, ocaml_field0.raw());
+                        }
+                        unsafe { ::ocaml_interop::OCaml::new(cr, block_root.get_raw()) }
+                    }
+                    PluginEvent::Stopped => {
+                        unsafe {
+                            ::ocaml_interop::OCaml::new(cr, { static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor = ::ocaml_interop::internal::ExtensionConstructor::new("plugin_event.Done"); CONSTRUCTOR.get() })
+                        }
+                    }
+                    PluginEvent::Progress { current: ref current, cache: _ } => {
+                        let block_root: ::ocaml_interop::BoxRoot<()> = ::ocaml_interop::BoxRoot::new(unsafe {
+                            ::ocaml_interop::OCaml::new(cr, ::ocaml_interop::internal::caml_alloc(2usize, 0))
+                        });
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(
+                                block_root.get_raw(),
+                                0usize,
+                                { static CONSTRUCTOR: ::ocaml_interop::internal::ExtensionConstructor = ::ocaml_interop::internal::ExtensionConstructor::new("plugin_event.Progress"); CONSTRUCTOR.get() },
+                            );
+                        }
+                        let ocaml_field0: ::ocaml_interop::OCaml<OCamlInt> = current.to_ocaml(cr);
+                        unsafe {
+                            ::ocaml_interop::internal::store_field(block_root.get_raw(), 1usize, ocaml_field0.raw());
+                        }
+                        unsafe { ::ocaml_interop::OCaml::new(cr, block_root.get_raw()) }
+                    }
+                }
+            }
+        }
+    };
+
+    let actual_impl = expand_to_ocaml(input_enum).unwrap();
+    assert_eq!(actual_impl.to_string(), expected_impl.to_string());
+}
+
+#[test]
+fn test_to_ocaml_extensible_errors() {
+    let cases = [
+        (
+            quote! {
+                #[ocaml(extensible = "config")]
+                struct Config { retries: i64 }
+            },
+            "#[ocaml(extensible)] is only applicable on enum types only",
+        ),
+        (
+            quote! {
+                #[ocaml(polymorphic_variant, extensible = "event")]
+                enum Event { Click }
+            },
+            "#[ocaml(extensible = \"...\")] can't be combined with #[ocaml(polymorphic_variant)]",
+        ),
+        (
+            quote! {
+                #[ocaml(extensible = "event")]
+                enum Event { #[ocaml(boxed_record)] Click { x: f64 } }
+            },
+            "#[ocaml(boxed_record)] is only applicable on variants with named fields of regular enums",
+        ),
+    ];
+    for (input, expected_message) in cases {
+        let err_msg = expand_to_ocaml(input).unwrap_err().to_string();
+        assert!(
+            err_msg.contains(expected_message),
+            "unexpected error message: {err_msg}"
+        );
+    }
+}
@@ -8,10 +8,12 @@ use syn::{DeriveInput, Ident};
 use crate::common::{
     field_processing::{
         create_ocaml_block, create_ocaml_tuple, create_polymorphic_variant_block,
-        generate_field_conversion_and_storage, generate_struct_field_conversions, get_ocaml_type,
-        make_field_var, TypeDirection,
+        extension_constructor_expr, field_to_ocaml_expr, generate_field_conversion_and_storage,
+        generate_struct_field_conversions, get_ocaml_type, make_field_var, TypeDirection,
+    },
+    parsing::{
+        ocaml_fields, parse_input, EnumKind, FieldRep, Recursion, TypeRep, TypeRepData, VariantKind,
     },
-    parsing::{parse_input, EnumKind, TypeRep, TypeRepData, VariantKind},
     polytag_utils::generate_polytag_hash,
     validation, OCamlInteropError, Result,
 };
@@ -56,7 +58,11 @@ fn generate_variant_pattern(
             .enumerate()
             .map(|(idx, field)| {
                 let field_ident = make_field_var(idx, Some(field.span));
-                (quote! { ref #field_ident }, field_ident)
+                if field.is_skipped() || field.recursion == Some(Recursion::Boxed) {
+                    (quote! { _ }, field_ident)
+                } else {
+                    (quote! { ref #field_ident }, field_ident)
+                }
             })
             .unzip(),
         VariantKind::Struct => variant_rep
@@ -68,7 +74,11 @@ fn generate_variant_pattern(
                     .as_ref()
                     .expect("Named field in struct variant must have an identifier")
                     .clone();
-                (quote! { #field_ident: ref #field_ident }, field_ident)
+                if field.is_skipped() || field.recursion == Some(Recursion::Boxed) {
+                    (quote! { #field_ident: _ }, field_ident)
+                } else {
+                    (quote! { #field_ident: ref #field_ident }, field_ident)
+                }
             })
             .unzip(),
         _ => unreachable!(),
@@ -98,8 +108,9 @@ fn generate_poly_variant_payload(
         let ocaml_payload_var = Ident::new(&format!("ocaml_payload_{field_ident}"), field_rep.span);
 
         let ocaml_type = get_ocaml_type(field_rep, TypeDirection::ToOCaml);
+        let conversion = field_to_ocaml_expr(field_rep, quote! { #field_ident });
         setup_ops.push(quote! {
-            let #ocaml_payload_var: ::ocaml_interop::OCaml<#ocaml_type> = #field_ident.to_ocaml(cr);
+            let #ocaml_payload_var: ::ocaml_interop::OCaml<#ocaml_type> = #conversion;
         });
 
         quote! { #ocaml_payload_var.raw() }
@@ -145,6 +156,7 @@ fn generate_field_conversions_with_suffix(
     fields
         .iter()
         .zip(field_idents.iter())
+        .filter(|(field_rep, _)| !field_rep.is_skipped())
         .enumerate()
         .map(|(idx, (field_rep, field_ident))| {
             let var_name = if suffix.is_empty() {
@@ -170,11 +182,18 @@ fn generate_regular_block_variant_arm(
     variant_rep: &crate::common::parsing::VariantRep,
     block_variant_idx: u8,
 ) -> TokenStream {
+    if variant_rep.is_boxed_record() {
+        return generate_boxed_record_variant_arm(type_ident, variant_rep, block_variant_idx);
+    }
+
     let (pattern, field_idents) = generate_variant_pattern(variant_rep);
     let variant_ident = &variant_rep.ident;
 
     let block_root_var = Ident::new("block_root", variant_rep.span);
-    let block_allocation = create_ocaml_block(variant_rep.fields.len(), block_variant_idx as usize);
+    let block_allocation = create_ocaml_block(
+        ocaml_fields(&variant_rep.fields).count(),
+        block_variant_idx as usize,
+    );
     let field_processing = generate_field_conversions(
         &variant_rep.fields,
         &field_idents,
@@ -190,6 +209,212 @@ fn generate_regular_block_variant_arm(
     }
 }
 
+/// Generate a regular enum variant arm for a `#[ocaml(boxed_record)]` variant, where the
+/// constructor block points to a separate record block
+fn generate_boxed_record_variant_arm(
+    type_ident: &Ident,
+    variant_rep: &crate::common::parsing::VariantRep,
+    block_variant_idx: u8,
+) -> TokenStream {
+    let (pattern, field_idents) = generate_variant_pattern(variant_rep);
+    let variant_ident = &variant_rep.ident;
+
+    let block_root_var = Ident::new("block_root", variant_rep.span);
+    let block_allocation = create_ocaml_block(1, block_variant_idx as usize);
+
+    if variant_rep.is_boxed_float_record() {
+        let float_record_stores = generate_float_record_stores(&variant_rep.fields, |field_rep| {
+            let field_ident = &field_rep.ident;
+            quote! { *#field_ident }
+        });
+        return quote! {
+            #type_ident::#variant_ident #pattern => {
+                let #block_root_var: ::ocaml_interop::BoxRoot<()> = #block_allocation;
+                unsafe {
+                    #float_record_stores
+                    ::ocaml_interop::internal::store_field(#block_root_var.get_raw(), 0usize, record);
+                    ::ocaml_interop::OCaml::new(cr, #block_root_var.get_raw())
+                }
+            }
+        };
+    }
+
+    let record_root_var = Ident::new("record_root", variant_rep.span);
+    let record_allocation = create_ocaml_block(ocaml_fields(&variant_rep.fields).count(), 0);
+    let field_processing = generate_field_conversions(
+        &variant_rep.fields,
+        &field_idents,
+        quote! { #record_root_var.get_raw() },
+    );
+
+    quote! {
+        #type_ident::#variant_ident #pattern => {
+            let #record_root_var: ::ocaml_interop::BoxRoot<()> = #record_allocation;
+            #(#field_processing)*
+            let #block_root_var: ::ocaml_interop::BoxRoot<()> = #block_allocation;
+            unsafe {
+                ::ocaml_interop::internal::store_field(#block_root_var.get_raw(), 0usize, #record_root_var.get_raw());
+                ::ocaml_interop::OCaml::new(cr, #block_root_var.get_raw())
+            }
+        }
+    }
+}
+
+/// Generate the body for an `[@@unboxed]` type, represented directly by its only field
+fn generate_unboxed_body(
+    field_rep: &crate::common::parsing::FieldRep,
+    field_access: TokenStream,
+) -> TokenStream {
+    let ocaml_type = get_ocaml_type(field_rep, TypeDirection::ToOCaml);
+    let conversion = field_to_ocaml_expr(field_rep, field_access);
+    quote! {
+        let unboxed: ::ocaml_interop::OCaml<#ocaml_type> = #conversion;
+        unsafe { unboxed.cast() }
+    }
+}
+
+/// Generate the statements that allocate a record of unboxed floats, stored in a
+/// `Double_array_tag` block, in a `record` variable and fill its fields
+fn generate_float_record_stores(
+    fields: &[crate::common::parsing::FieldRep],
+    field_access: impl Fn(&crate::common::parsing::FieldRep) -> TokenStream,
+) -> TokenStream {
+    let size_lit = syn::LitInt::new(
+        &format!("{}usize", ocaml_fields(fields).count()),
+        Span::call_site(),
+    );
+    let field_stores = ocaml_fields(fields).enumerate().map(|(idx, field_rep)| {
+        let idx_lit = syn::LitInt::new(&format!("{idx}usize"), Span::call_site());
+        let field_access = field_access(field_rep);
+        quote! {
+            ::ocaml_interop::internal::store_double_field(record, #idx_lit, #field_access);
+        }
+    });
+
+    quote! {
+        let record = ::ocaml_interop::internal::alloc_float_record(#size_lit);
+        #(#field_stores)*
+    }
+}
+
+/// Generate the body for a record of unboxed floats, stored in a `Double_array_tag` block
+fn generate_float_record_body(fields: &[crate::common::parsing::FieldRep]) -> TokenStream {
+    let float_record_stores = generate_float_record_stores(fields, |field_rep| {
+        let field_ident = field_rep
+            .ident
+            .as_ref()
+            .expect("Float record fields must have identifiers");
+        quote! { self.#field_ident }
+    });
+
+    quote! {
+        unsafe {
+            #float_record_stores
+            ::ocaml_interop::OCaml::new(cr, record)
+        }
+    }
+}
+
+/// The value being converted, `self`, or the current `node` for recursive types
+fn conversion_receiver(type_rep: &TypeRep) -> TokenStream {
+    if type_rep.is_recursive() {
+        quote! { node }
+    } else {
+        quote! { self }
+    }
+}
+
+/// Generate the bindings of the recursive fields, for a `Path { field: child0, .. }` pattern,
+/// and the statements that push their values to `stack`, in field order, so that they are
+/// converted last field first
+fn generate_child_pushes(fields: &[FieldRep]) -> (Vec<TokenStream>, Vec<TokenStream>) {
+    fields
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, field_rep)| {
+            let recursion = field_rep.recursion?;
+            let member = match &field_rep.ident {
+                Some(ident) => quote! { #ident },
+                None => {
+                    let i = syn::Index::from(idx);
+                    quote! { #i }
+                }
+            };
+            let child_var = Ident::new(&format!("child{idx}"), Span::call_site());
+            let push = match recursion {
+                Recursion::Boxed => quote! {
+                    stack.push((&**#child_var, false));
+                },
+                Recursion::OptionalBoxed => quote! {
+                    if let Some(child) = #child_var {
+                        stack.push((&**child, false));
+                    }
+                },
+                Recursion::List => quote! {
+                    for child in #child_var {
+                        stack.push((child, false));
+                    }
+                },
+            };
+            Some((quote! { #member: #child_var }, push))
+        })
+        .unzip()
+}
+
+/// Wraps `build`, the conversion of `node` once its recursive fields have been converted, in a
+/// loop that converts the values of recursive types with an explicit stack instead of recursive
+/// calls. Values are converted after the values of their recursive fields, which are kept
+/// rooted in `converted` until they are stored. Non-recursive types are converted by `build`
+/// directly.
+fn wrap_recursive_conversion(type_rep: &TypeRep, build: TokenStream) -> TokenStream {
+    if !type_rep.is_recursive() {
+        return build;
+    }
+
+    let type_ident = &type_rep.ident;
+    let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
+    let child_pushes = match &type_rep.data {
+        TypeRepData::Struct { fields } => {
+            let (bindings, pushes) = generate_child_pushes(fields);
+            quote! {
+                let #type_ident { #(#bindings,)* .. } = node;
+                #(#pushes)*
+            }
+        }
+        TypeRepData::Enum { variants, .. } => {
+            let arms = variants.iter().map(|variant_rep| {
+                let variant_ident = &variant_rep.ident;
+                let (bindings, pushes) = generate_child_pushes(&variant_rep.fields);
+                quote! {
+                    #type_ident::#variant_ident { #(#bindings,)* .. } => {
+                        #(#pushes)*
+                    }
+                }
+            });
+            quote! {
+                match node {
+                    #(#arms)*
+                }
+            }
+        }
+    };
+
+    quote! {
+        let mut stack: Vec<(&Self, bool)> = vec![(self, false)];
+        let mut converted: Vec<::ocaml_interop::BoxRoot<#ocaml_type_ident_token_stream>> = Vec::new();
+        while let Some((node, children_converted)) = stack.pop() {
+            if children_converted {
+                let ocaml_node: ::ocaml_interop::OCaml<#ocaml_type_ident_token_stream> = { #build };
+                converted.push(ocaml_node.root());
+            } else {
+                stack.push((node, true));
+                #child_pushes
+            }
+        }
+        converted.pop().expect("the converted value should be the last one left").get(cr)
+    }
+}
+
 fn codegen_struct_impl(
     type_rep: &TypeRep,
     fields: &[crate::common::parsing::FieldRep],
@@ -198,17 +423,52 @@ fn codegen_struct_impl(
     let (impl_generics, ty_generics, where_clause) = type_rep.generics.split_for_impl();
     let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
 
+    if type_rep.attrs.is_unboxed() {
+        let (idx, field_rep) = fields
+            .iter()
+            .enumerate()
+            .find(|(_, field_rep)| !field_rep.is_skipped())
+            .expect("Unboxed structs must have one field");
+        let field_access = match &field_rep.ident {
+            Some(ident) => quote! { self.#ident },
+            None => {
+                let i = syn::Index::from(idx);
+                quote! { self.#i }
+            }
+        };
+        let body = generate_unboxed_body(field_rep, field_access);
+        return quote! {
+            unsafe impl #impl_generics ::ocaml_interop::ToOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
+                fn to_ocaml<'a>(&self, cr: &'a mut ::ocaml_interop::OCamlRuntime) -> ::ocaml_interop::OCaml<'a, #ocaml_type_ident_token_stream #ty_generics> {
+                    #body
+                }
+            }
+        };
+    }
+
+    if type_rep.is_float_record() {
+        let body = generate_float_record_body(fields);
+        return quote! {
+            unsafe impl #impl_generics ::ocaml_interop::ToOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
+                fn to_ocaml<'a>(&self, cr: &'a mut ::ocaml_interop::OCamlRuntime) -> ::ocaml_interop::OCaml<'a, #ocaml_type_ident_token_stream #ty_generics> {
+                    #body
+                }
+            }
+        };
+    }
+
+    let receiver = conversion_receiver(type_rep);
     let record_var = Ident::new("record_root", proc_macro2::Span::call_site());
-    let record_allocation = create_ocaml_block(fields.len(), 0); // Tag 0 for records/structs
+    let record_allocation = create_ocaml_block(ocaml_fields(fields).count(), 0); // Tag 0 for records/structs
 
     let container_expr = quote! { #record_var.get_raw() };
     let field_processing_quotes =
         generate_struct_field_conversions(fields, container_expr, |idx, field_rep| {
             if let Some(ident) = &field_rep.ident {
-                quote! { self.#ident }
+                quote! { #receiver.#ident }
             } else {
                 let i = syn::Index::from(idx);
-                quote! { self.#i }
+                quote! { #receiver.#i }
             }
         });
 
@@ -217,6 +477,7 @@ fn codegen_struct_impl(
         #(#field_processing_quotes)*
         unsafe { ::ocaml_interop::OCaml::new(cr, #record_var.get_raw()) }
     };
+    let body = wrap_recursive_conversion(type_rep, body);
 
     quote! {
         unsafe impl #impl_generics ::ocaml_interop::ToOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
@@ -237,21 +498,122 @@ fn codegen_enum_impl(
     let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
 
     let match_arms = match kind {
+        EnumKind::Regular if type_rep.attrs.is_unboxed() => {
+            generate_unboxed_enum_arms(type_ident, variants)
+        }
         EnumKind::Regular => generate_regular_enum_arms(type_ident, variants),
         EnumKind::Polymorphic => generate_polymorphic_enum_arms(type_ident, variants),
+        EnumKind::Extensible => generate_extensible_enum_arms(type_rep, variants),
     };
 
+    let receiver = conversion_receiver(type_rep);
+    let body = wrap_recursive_conversion(
+        type_rep,
+        quote! {
+            match #receiver {
+                #(#match_arms)*
+            }
+        },
+    );
+
     quote! {
         unsafe impl #impl_generics ::ocaml_interop::ToOCaml<#ocaml_type_ident_token_stream #ty_generics> for #type_ident #ty_generics #where_clause {
             fn to_ocaml<'a>(&self, cr: &'a mut ::ocaml_interop::OCamlRuntime) -> ::ocaml_interop::OCaml<'a, #ocaml_type_ident_token_stream #ty_generics> {
-                match self {
-                    #(#match_arms)*
-                }
+                #body
             }
         }
     }
 }
 
+/// Generate the match arm for the only variant of an `[@@unboxed]` enum
+fn generate_unboxed_enum_arms(
+    type_ident: &Ident,
+    variants: &[crate::common::parsing::VariantRep],
+) -> Vec<TokenStream> {
+    let variant_rep = &variants[0];
+    let variant_ident = &variant_rep.ident;
+    let (pattern, field_idents) = generate_variant_pattern(variant_rep);
+    let (field_rep, field_ident) = variant_rep
+        .fields
+        .iter()
+        .zip(field_idents.iter())
+        .find(|(field_rep, _)| !field_rep.is_skipped())
+        .expect("Unboxed variants must have one field");
+    let body = generate_unboxed_body(field_rep, quote! { #field_ident });
+    vec![quote! {
+        #type_ident::#variant_ident #pattern => {
+            #body
+        }
+    }]
+}
+
+/// Generate match arms for `#[ocaml(extensible = "...")]` enums. Constructors without
+/// arguments are represented by their extension constructor, and the others by a block with
+/// tag 0 that stores the extension constructor followed by the arguments.
+fn generate_extensible_enum_arms(
+    type_rep: &TypeRep,
+    variants: &[crate::common::parsing::VariantRep],
+) -> Vec<TokenStream> {
+    let type_ident = &type_rep.ident;
+
+    variants
+        .iter()
+        .map(|variant_rep| {
+            let variant_ident = &variant_rep.ident;
+            let constructor_name = type_rep
+                .extension_constructor_name(variant_rep)
+                .expect("Extensible enums must have a registered name");
+            let expected_constructor = extension_constructor_expr(&constructor_name);
+
+            if variant_rep.kind == VariantKind::Unit {
+                return quote! {
+                    #type_ident::#variant_ident => {
+                        unsafe {
+                            ::ocaml_interop::OCaml::new(cr, #expected_constructor)
+                        }
+                    }
+                };
+            }
+
+            let (pattern, field_idents) = generate_variant_pattern(variant_rep);
+            let block_root_var = Ident::new("block_root", variant_rep.span);
+            let block_allocation =
+                create_ocaml_block(ocaml_fields(&variant_rep.fields).count() + 1, 0);
+            let field_processing = variant_rep
+                .fields
+                .iter()
+                .zip(field_idents.iter())
+                .filter(|(field_rep, _)| !field_rep.is_skipped())
+                .enumerate()
+                .map(|(idx, (field_rep, field_ident))| {
+                    let ocaml_field_var = Ident::new(&format!("ocaml_field{idx}"), field_rep.span);
+                    generate_field_conversion_and_storage(
+                        field_rep,
+                        quote! { #field_ident },
+                        &ocaml_field_var,
+                        quote! { #block_root_var.get_raw() },
+                        idx + 1,
+                    )
+                });
+
+            quote! {
+                #type_ident::#variant_ident #pattern => {
+                    let #block_root_var: ::ocaml_interop::BoxRoot<()> = #block_allocation;
+                    unsafe {
+                        ::ocaml_interop::internal::store_field(
+                            #block_root_var.get_raw(),
+                            0usize,
+                            #expected_constructor,
+                        );
+                    }
+                    #(#field_processing)*
+                    unsafe { ::ocaml_interop::OCaml::new(cr, #block_root_var.get_raw()) }
+                }
+            }
+        })
+        .collect()
+}
+
 /// Generate match arms for regular enums
 fn generate_regular_enum_arms(
     type_ident: &Ident,
@@ -316,9 +678,101 @@ fn generate_polymorphic_enum_arms(
         .collect()
 }
 
+/// Generate the `<Type>Setters` extension trait with a setter for each `#[ocaml(mutable)]`
+/// field, implemented for rooted values (`OCamlRef`, and `BoxRoot` through `Deref`)
+fn codegen_setters(type_rep: &TypeRep, fields: &[crate::common::parsing::FieldRep]) -> TokenStream {
+    if !fields.iter().any(|field_rep| field_rep.attrs.is_mutable()) {
+        return quote! {};
+    }
+
+    let type_ident = &type_rep.ident;
+    let vis = &type_rep.vis;
+    let ocaml_type_ident_token_stream = &type_rep.ocaml_target_type_ident_ts;
+    let trait_ident = Ident::new(&format!("{type_ident}Setters"), type_ident.span());
+    let is_float_record = type_rep.is_float_record();
+
+    let (signatures, methods): (Vec<_>, Vec<_>) = ocaml_fields(fields)
+        .enumerate()
+        .filter(|(_, field_rep)| field_rep.attrs.is_mutable())
+        .map(|(idx, field_rep)| {
+            let field_ident = field_rep
+                .ident
+                .as_ref()
+                .expect("Mutable fields must have identifiers");
+            let field_ty = &field_rep.ty;
+            let setter_ident = Ident::new(&format!("set_{field_ident}"), field_ident.span());
+            let idx_lit = syn::LitInt::new(&format!("{idx}usize"), Span::call_site());
+            let doc = format!("Sets the `{field_ident}` field of the record.");
+            let signature = |cr: Ident| {
+                quote! {
+                    fn #setter_ident(self, #cr: &mut ::ocaml_interop::OCamlRuntime, value: #field_ty)
+                }
+            };
+            let cr_ident = Ident::new("cr", Span::call_site());
+            let (impl_signature, body) = if is_float_record {
+                let body = quote! {
+                    unsafe { ::ocaml_interop::internal::store_double_field(self.get_raw(), #idx_lit, value) }
+                };
+                (signature(Ident::new("_cr", Span::call_site())), body)
+            } else {
+                let ocaml_type = get_ocaml_type(field_rep, TypeDirection::ToOCaml);
+                let conversion = field_to_ocaml_expr(field_rep, quote! { value });
+                let body = quote! {
+                    let ocaml_value: ::ocaml_interop::OCaml<#ocaml_type> = #conversion;
+                    unsafe { ::ocaml_interop::internal::store_field(self.get_raw(), #idx_lit, ocaml_value.raw()) }
+                };
+                (signature(cr_ident.clone()), body)
+            };
+            let trait_signature = signature(cr_ident);
+            (
+                quote! {
+                    #[doc = #doc]
+                    #trait_signature;
+                },
+                quote! {
+                    #impl_signature {
+                        #body
+                    }
+                },
+            )
+        })
+        .unzip();
+
+    let trait_doc = format!(
+        "Setters for the mutable fields of rooted OCaml `{type_ident}` records, generated by `#[derive(ToOCaml)]`.\n\n\
+         Implemented for `OCamlRef`, and so usable on `BoxRoot` values. Setting a field needs \
+         `&mut OCamlRuntime`, which can't be used while an `OCaml` value borrowed from it is \
+         alive, and converting non-float fields allocates, which could move an unrooted record."
+    );
+
+    // Only phantom type parameters are allowed, see `validate_mutable_fields`
+    let mut impl_generics = type_rep.generics.clone();
+    impl_generics.params.insert(0, syn::parse_quote! { 'r });
+    let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+    let (_, ty_generics, _) = type_rep.generics.split_for_impl();
+
+    quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_ident {
+            #(#signatures)*
+        }
+
+        impl #impl_generics #trait_ident for ::ocaml_interop::OCamlRef<'r, #ocaml_type_ident_token_stream #ty_generics> #where_clause {
+            #(#methods)*
+        }
+    }
+}
+
 fn codegen_to_ocaml(type_rep: &TypeRep) -> TokenStream {
     match &type_rep.data {
-        TypeRepData::Struct { fields } => codegen_struct_impl(type_rep, fields),
+        TypeRepData::Struct { fields } => {
+            let to_ocaml_impl = codegen_struct_impl(type_rep, fields);
+            let setters = codegen_setters(type_rep, fields);
+            quote! {
+                #to_ocaml_impl
+                #setters
+            }
+        }
         TypeRepData::Enum { variants, kind } => codegen_enum_impl(type_rep, variants, kind),
     }
 }
@@ -0,0 +1,147 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::marker::PhantomData;
+
+use ocaml_sys::{caml_callback2_exn, caml_callback3_exn, caml_callbackN_exn, caml_callback_exn};
+
+use crate::{
+    boxroot::BoxRoot, mlvalues::RawOCaml, OCaml, OCamlException, OCamlParam, OCamlRuntime,
+};
+
+/// A rooted OCaml closure taking the arguments in the `Args` tuple and returning `Ret`.
+///
+/// Exported functions can take `OCamlCallback` arguments directly, in which case the
+/// closure is rooted before the function body runs:
+///
+/// ```rust,no_run
+/// use ocaml_interop::{OCaml, OCamlCallback, OCamlInt, OCamlRuntime};
+///
+/// #[ocaml_interop::export]
+/// pub fn rust_iter_range(
+///     cr: &mut OCamlRuntime,
+///     count: OCaml<OCamlInt>,
+///     f: OCamlCallback<(OCamlInt,), ()>,
+/// ) -> OCaml<()> {
+///     let count: i64 = count.to_rust();
+///     for i in 0..count {
+///         if let Err(exn) = f.call(cr, i) {
+///             panic!("callback raised: {:?}", exn.message());
+///         }
+///     }
+///     OCaml::unit()
+/// }
+/// ```
+///
+/// On the OCaml side the argument is a regular closure (`int -> unit` in the example).
+///
+/// Arguments passed to [`OCamlCallback::call`] are rooted for the duration of the call,
+/// and exceptions raised by the closure are returned as `Err`.
+pub struct OCamlCallback<Args, Ret: 'static> {
+    closure: BoxRoot<()>,
+    _marker: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<Args, Ret> OCamlCallback<Args, Ret> {
+    /// Roots the OCaml closure in `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be an OCaml closure of the expected type, and the current thread must
+    /// hold the domain lock.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(raw: RawOCaml) -> Self {
+        OCamlCallback {
+            closure: BoxRoot::new(OCaml {
+                _marker: PhantomData,
+                raw,
+            }),
+            _marker: PhantomData,
+        }
+    }
+
+    unsafe fn raw_closure(&self) -> RawOCaml {
+        self.closure.get_raw()
+    }
+}
+
+macro_rules! ocaml_callback_impl {
+    (
+        $( { $method:ident, ($( ($argname:ident: $ot:ident $rt:ident) ),+) } ),*,
+        NPARAMS:
+        $( { $( ($argname2:ident: $ot2:ident $rt2:ident) ),+ } ),*,
+    ) => {
+        $(
+            #[allow(non_camel_case_types)]
+            impl<$($ot: 'static,)+ Ret> OCamlCallback<($($ot,)+), Ret> {
+                /// Roots `closure`.
+                pub fn new(closure: OCaml<fn($($ot,)+) -> Ret>) -> Self {
+                    unsafe { Self::from_raw(closure.raw()) }
+                }
+
+                /// Calls the OCaml closure, converting the arguments to OCaml if necessary.
+                ///
+                /// Returns `Err` with the exception if the closure raised one.
+                pub fn call<'c, 'a, 'b: 'a, $($argname,)+ $($rt),+>(
+                    &self,
+                    cr: &'c mut OCamlRuntime,
+                    $($argname: $argname),+
+                ) -> Result<OCaml<'c, Ret>, OCaml<'c, OCamlException>>
+                where
+                    $($argname: OCamlParam<'a, 'b, $rt, $ot>),+
+                {
+                    $(let $argname = $argname.to_rooted(cr);)+
+
+                    let result = unsafe { $method(self.raw_closure(), $($argname.get_raw()),+) };
+                    match unsafe { OCaml::of_exception_result(cr, result) } {
+                        Some(ex) => Err(ex),
+                        None => Ok(unsafe { OCaml::new(cr, result) }),
+                    }
+                }
+            }
+        )*
+        $(
+            #[allow(clippy::too_many_arguments)]
+            #[allow(non_camel_case_types)]
+            impl<$($ot2: 'static,)+ Ret> OCamlCallback<($($ot2,)+), Ret> {
+                /// Roots `closure`.
+                pub fn new(closure: OCaml<fn($($ot2,)+) -> Ret>) -> Self {
+                    unsafe { Self::from_raw(closure.raw()) }
+                }
+
+                /// Calls the OCaml closure, converting the arguments to OCaml if necessary.
+                ///
+                /// Returns `Err` with the exception if the closure raised one.
+                pub fn call<'c, 'a, 'b: 'a, $($argname2,)+ $($rt2),+>(
+                    &self,
+                    cr: &'c mut OCamlRuntime,
+                    $($argname2: $argname2),+
+                ) -> Result<OCaml<'c, Ret>, OCaml<'c, OCamlException>>
+                where
+                    $($argname2: OCamlParam<'a, 'b, $rt2, $ot2>),+
+                {
+                    $(let $argname2 = $argname2.to_rooted(cr);)+
+
+                    let mut args = unsafe { [$($argname2.get_raw()),+] };
+                    let result = unsafe {
+                        caml_callbackN_exn(self.raw_closure(), args.len(), args.as_mut_ptr())
+                    };
+                    match unsafe { OCaml::of_exception_result(cr, result) } {
+                        Some(ex) => Err(ex),
+                        None => Ok(unsafe { OCaml::new(cr, result) }),
+                    }
+                }
+            }
+        )*
+    }
+}
+
+ocaml_callback_impl! {
+    { caml_callback_exn, ((arg1: OCaml1 Rust1)) },
+    { caml_callback2_exn, ((arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2)) },
+    { caml_callback3_exn, ((arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3)) },
+    NPARAMS:
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3), (arg4: OCaml4 Rust4) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3), (arg4: OCaml4 Rust4),
+       (arg5: OCaml5 Rust5) },
+}
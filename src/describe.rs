@@ -9,6 +9,27 @@ use crate::{OCamlFloatArray, OCamlUniformArray};
 
 pub trait OCamlDescriber {
     fn ocaml_type_name() -> String;
+
+    /// The OCaml declaration of the type, e.g. `type point = { x : float; y : float }`.
+    ///
+    /// `None` for builtin types. `#[derive(OCamlDescriber)]` implements it for non-generic
+    /// structs and enums marked with `#[ocaml(definition)]`, which requires the OCaml types of
+    /// all their fields to implement `OCamlDescriber`.
+    fn ocaml_type_definition() -> Option<String> {
+        None
+    }
+
+    /// The definition of the type without the `type` keyword, e.g.
+    /// `point = { x : float; y : float }`, to be joined with `and` in a group of mutually
+    /// recursive definitions.
+    ///
+    /// `#[derive(OCamlDescriber)]` implements it along with [`ocaml_type_definition`], which
+    /// includes the types listed in `#[ocaml(and = "OtherType, ...")]` in the same group.
+    ///
+    /// [`ocaml_type_definition`]: OCamlDescriber::ocaml_type_definition
+    fn ocaml_type_binding() -> Option<String> {
+        None
+    }
 }
 
 impl OCamlDescriber for bool {
@@ -0,0 +1,278 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::{
+    any::Any,
+    ffi::CString,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+};
+
+use ocaml_sys::{caml_callback_exn, caml_named_value};
+
+use crate::{
+    boxroot::BoxRoot,
+    mlvalues::{RawOCaml, UNIT},
+    runtime::domain_lock_held,
+    rust_closure::alloc_rust_closure1,
+    OCaml, OCamlRuntime, SendableRoot,
+};
+
+type Job = Box<dyn FnOnce(&mut OCamlRuntime) + Send>;
+
+const DOMAIN_SPAWN_NAME: &str = "ocaml_interop_domain_spawn";
+const DOMAIN_JOIN_NAME: &str = "ocaml_interop_domain_join";
+
+// Marker for the `unit Domain.t` handle returned by the spawning function.
+enum DomainHandle {}
+
+/// An OCaml 5 domain spawned from Rust that runs closures sent to it.
+///
+/// Each domain has its own domain lock, so closures running on different domains run
+/// in parallel, each with its own `&mut OCamlRuntime`. Closures sent to the same domain
+/// run one after the other, in the order they were sent.
+///
+/// Requires the `ocaml_interop_closure_call1` dispatcher (see [`OCamlRuntime::finalise`])
+/// and the domain spawning and joining functions to be registered from the OCaml side:
+///
+/// ```ocaml
+/// let () =
+///   Callback.register "ocaml_interop_domain_spawn" Domain.spawn;
+///   Callback.register "ocaml_interop_domain_join" Domain.join
+/// ```
+///
+/// The domain keeps running until it is joined with [`OCamlDomain::join`] or the handle
+/// is dropped. Dropping the handle stops the domain without waiting for it to terminate.
+pub struct OCamlDomain {
+    jobs: mpsc::Sender<Job>,
+    finished: Arc<(Mutex<bool>, Condvar)>,
+    handle: SendableRoot<DomainHandle>,
+}
+
+impl OCamlDomain {
+    /// Spawns a new OCaml domain.
+    ///
+    /// Returns `Err(String)` if the spawning function is not registered or if OCaml
+    /// failed to spawn the domain (for example when the maximum number of domains
+    /// has been reached).
+    pub fn spawn(cr: &mut OCamlRuntime) -> Result<OCamlDomain, String> {
+        let spawn = named_closure(DOMAIN_SPAWN_NAME)?;
+        named_closure(DOMAIN_JOIN_NAME)?;
+
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let finished = Arc::new((Mutex::new(false), Condvar::new()));
+        let finished_signal = finished.clone();
+        let mut receiver = Some(receiver);
+        let body = alloc_rust_closure1::<(), ()>(cr, move |cr, _unit| {
+            if let Some(receiver) = receiver.take() {
+                run_domain_loop(cr, receiver);
+                let (lock, condvar) = &*finished_signal;
+                *lock.lock().unwrap() = true;
+                condvar.notify_all();
+            }
+            UNIT
+        });
+        let body = BoxRoot::new(body);
+
+        let result = unsafe { caml_callback_exn(*spawn, body.get_raw()) };
+        if let Some(exception) = unsafe { OCaml::of_exception_result(cr, result) } {
+            return Err(match exception.message() {
+                Some(message) => format!("Failed to spawn OCaml domain: {message}"),
+                None => "Failed to spawn OCaml domain".to_string(),
+            });
+        }
+
+        let handle = unsafe { OCaml::<DomainHandle>::new(cr, result) };
+        Ok(OCamlDomain {
+            jobs,
+            finished,
+            handle: SendableRoot::new(handle),
+        })
+    }
+
+    /// Runs `f` on this domain and waits for its result.
+    ///
+    /// Panics in `f` are propagated to the caller.
+    ///
+    /// Must not be called while holding a domain lock, because the domain may need the
+    /// cooperation of all the other domains to make progress (for example to perform a
+    /// minor collection). Use [`OCamlRuntime::releasing_runtime`] when calling it from code
+    /// that holds a runtime handle.
+    ///
+    /// Panics if the current thread is known to hold a domain lock.
+    pub fn run<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut OCamlRuntime) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        assert_domain_lock_not_held("OCamlDomain::run");
+        let (sender, receiver) = mpsc::sync_channel::<Result<T, Box<dyn Any + Send>>>(1);
+        self.execute(move |cr| {
+            let result = catch_unwind(AssertUnwindSafe(|| f(cr)));
+            let _ = sender.send(result);
+        });
+        match receiver.recv() {
+            Ok(Ok(value)) => value,
+            Ok(Err(panic_payload)) => resume_unwind(panic_payload),
+            Err(_) => panic!("OCaml domain terminated before running the closure"),
+        }
+    }
+
+    /// Runs `f` on this domain without waiting for it to finish.
+    ///
+    /// Panics in `f` are caught and discarded.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce(&mut OCamlRuntime) + Send + 'static,
+    {
+        self.jobs
+            .send(Box::new(f))
+            .expect("OCaml domain is not running");
+    }
+
+    /// Stops the domain after it finishes running the closures already sent to it,
+    /// and waits for it to terminate with `Domain.join`.
+    ///
+    /// Like [`OCamlDomain::run`], must not be called while holding a domain lock.
+    pub fn join(self) {
+        assert_domain_lock_not_held("OCamlDomain::join");
+        let OCamlDomain {
+            jobs,
+            finished,
+            handle,
+        } = self;
+        drop(jobs);
+        {
+            let (lock, condvar) = &*finished;
+            let mut done = lock.lock().unwrap();
+            while !*done {
+                done = condvar.wait(done).unwrap();
+            }
+        }
+        OCamlRuntime::with_domain_lock(|cr| {
+            // Checked in `OCamlDomain::spawn`, and named values can't be unregistered.
+            let join = named_closure(DOMAIN_JOIN_NAME).unwrap();
+            let result = unsafe { caml_callback_exn(*join, handle.get_ref(cr).get_raw()) };
+            drop(handle);
+            if let Some(exception) = unsafe { OCaml::of_exception_result(cr, result) } {
+                match exception.message() {
+                    Some(message) => panic!("Failed to join OCaml domain: {message}"),
+                    None => panic!("Failed to join OCaml domain"),
+                }
+            }
+        });
+    }
+}
+
+fn named_closure(name: &str) -> Result<*const RawOCaml, String> {
+    let closure = unsafe {
+        let c_name = CString::new(name).unwrap();
+        caml_named_value(c_name.as_ptr())
+    };
+    if closure.is_null() {
+        Err(format!("OCaml closure with name '{name}' not registered"))
+    } else {
+        Ok(closure)
+    }
+}
+
+fn assert_domain_lock_not_held(operation: &str) {
+    assert!(
+        !domain_lock_held(),
+        "{operation} must not be called while holding a domain lock"
+    );
+}
+
+fn run_domain_loop(cr: &mut OCamlRuntime, receiver: mpsc::Receiver<Job>) {
+    // The domain lock is released while waiting, so that this domain doesn't hold up
+    // stop-the-world collections.
+    while let Ok(job) = cr.releasing_runtime(|| receiver.recv()) {
+        let _ = catch_unwind(AssertUnwindSafe(|| job(cr)));
+    }
+}
+
+/// A fixed set of [`OCamlDomain`]s that closures are distributed across.
+///
+/// ```rust,no_run
+/// use ocaml_interop::{OCamlDomainPool, OCamlRuntime};
+///
+/// let pool = OCamlRuntime::with_domain_lock(|cr| OCamlDomainPool::new(cr, 4)).unwrap();
+/// let results: Vec<i64> = (0..16)
+///     .map(|i| pool.run(move |_cr| i * 2))
+///     .collect();
+/// pool.join();
+/// ```
+pub struct OCamlDomainPool {
+    domains: Vec<OCamlDomain>,
+    next: AtomicUsize,
+}
+
+impl OCamlDomainPool {
+    /// Spawns a pool of `size` domains.
+    ///
+    /// Returns `Err(String)` if any of the domains couldn't be spawned, see
+    /// [`OCamlDomain::spawn`]. The domains spawned until then are stopped.
+    pub fn new(cr: &mut OCamlRuntime, size: usize) -> Result<OCamlDomainPool, String> {
+        assert!(size > 0, "OCaml domain pool size must be greater than 0");
+        let domains = (0..size)
+            .map(|_| OCamlDomain::spawn(cr))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(OCamlDomainPool {
+            domains,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Number of domains in the pool.
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    /// Always `false`, pools have at least one domain.
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Gets the domain at `index`, to pin closures to a specific domain.
+    pub fn domain(&self, index: usize) -> Option<&OCamlDomain> {
+        self.domains.get(index)
+    }
+
+    /// Runs `f` on the next domain in the pool and waits for its result.
+    ///
+    /// See [`OCamlDomain::run`].
+    pub fn run<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut OCamlRuntime) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.next_domain().run(f)
+    }
+
+    /// Runs `f` on the next domain in the pool without waiting for it to finish.
+    ///
+    /// See [`OCamlDomain::execute`].
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce(&mut OCamlRuntime) + Send + 'static,
+    {
+        self.next_domain().execute(f)
+    }
+
+    /// Stops all the domains in the pool and waits for them to terminate.
+    ///
+    /// See [`OCamlDomain::join`].
+    pub fn join(self) {
+        for domain in self.domains {
+            domain.join();
+        }
+    }
+
+    fn next_domain(&self) -> &OCamlDomain {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.domains.len();
+        &self.domains[index]
+    }
+}
@@ -1,7 +1,9 @@
 // Copyright (c) Viable Systems and TezEdge Contributors
 // SPDX-License-Identifier: MIT
 
-use crate::mlvalues::{MAX_FIXNUM, MIN_FIXNUM};
+use crate::global_root::SendableRoot;
+use crate::mlvalues::{OCamlException, MAX_FIXNUM, MIN_FIXNUM};
+use crate::OCaml;
 use core::fmt;
 
 #[derive(Debug)]
@@ -24,3 +26,63 @@ impl fmt::Display for OCamlFixnumConversionError {
         }
     }
 }
+
+/// Error returned by [`OCamlRuntime::with_domain_lock_timeout`](crate::OCamlRuntime::with_domain_lock_timeout)
+/// when the domain lock couldn't be acquired in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OCamlDomainLockTimeout;
+
+impl fmt::Display for OCamlDomainLockTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Timed out waiting for the OCaml domain lock")
+    }
+}
+
+impl std::error::Error for OCamlDomainLockTimeout {}
+
+/// Error for an OCaml exception raised while calling OCaml from Rust.
+///
+/// Keeps the exception itself rooted, and since it is `Send + Sync` it can be
+/// propagated with `?` through regular Rust error handling.
+pub struct OCamlExceptionError {
+    message: Option<String>,
+    exception: SendableRoot<OCamlException>,
+}
+
+impl OCamlExceptionError {
+    pub(crate) fn new(exception: OCaml<OCamlException>) -> Self {
+        OCamlExceptionError {
+            message: exception.message(),
+            exception: SendableRoot::new(exception),
+        }
+    }
+
+    /// The message of the exception, if it has one (like `Failure` and `Invalid_argument`).
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// The exception value.
+    pub fn exception(&self) -> &SendableRoot<OCamlException> {
+        &self.exception
+    }
+}
+
+impl fmt::Debug for OCamlExceptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OCamlExceptionError")
+            .field("message", &self.message)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for OCamlExceptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "OCaml exception raised: {message}"),
+            None => write!(f, "OCaml exception raised"),
+        }
+    }
+}
+
+impl std::error::Error for OCamlExceptionError {}
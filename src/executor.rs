@@ -0,0 +1,157 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::{
+    any::Any,
+    future::Future,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe},
+    pin::Pin,
+    sync::{mpsc, Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use crate::{OCamlDomainPool, OCamlRuntime};
+
+type Job = Box<dyn FnOnce(&mut OCamlRuntime) + Send>;
+
+enum Backend {
+    Thread(mpsc::Sender<Job>),
+    Domains(OCamlDomainPool),
+}
+
+/// A handle for running closures on the OCaml runtime from async code.
+///
+/// Calling [`OCamlRuntime::with_domain_lock`] from an async task blocks the executor
+/// thread until the domain lock is available. Instead, [`OCamlExecutor::spawn`] sends the
+/// closure to a dedicated worker and returns an [`OCamlTask`] future that resolves to its
+/// result, so the calling task is suspended rather than blocked.
+///
+/// Closures are `Send + 'static` and so are their results: OCaml values must be converted
+/// to Rust values (or rooted in a sendable handle) before being returned.
+///
+/// The executor doesn't depend on any particular async runtime.
+///
+/// ```rust,no_run
+/// use ocaml_interop::{OCamlExecutor, OCaml, ToOCaml};
+///
+/// async fn greeting_length(executor: &OCamlExecutor, name: String) -> usize {
+///     executor
+///         .spawn(move |cr| {
+///             let name: OCaml<String> = name.to_ocaml(cr);
+///             name.as_str().len()
+///         })
+///         .await
+/// }
+/// ```
+///
+/// Cloning an `OCamlExecutor` produces a new handle to the same workers. The workers stop
+/// after the last handle is dropped and all the pending closures have run.
+#[derive(Clone)]
+pub struct OCamlExecutor {
+    backend: Arc<Backend>,
+}
+
+impl OCamlExecutor {
+    /// Creates an executor backed by a dedicated Rust thread that runs the closures one
+    /// after the other, acquiring the domain lock with [`OCamlRuntime::with_domain_lock`]
+    /// for each of them.
+    ///
+    /// The runtime must have been initialized.
+    pub fn new() -> OCamlExecutor {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("ocaml-executor".to_string())
+            .spawn(move || {
+                while let Ok(job) = receiver.recv() {
+                    OCamlRuntime::with_domain_lock(job);
+                }
+            })
+            .expect("failed to spawn the OCaml executor thread");
+        OCamlExecutor {
+            backend: Arc::new(Backend::Thread(jobs)),
+        }
+    }
+
+    /// Creates an executor backed by a pool of `domains` OCaml domains, running closures in
+    /// parallel. See [`OCamlDomainPool`] for the OCaml side requirements.
+    ///
+    /// Returns `Err(String)` if the domains couldn't be spawned.
+    pub fn with_domains(cr: &mut OCamlRuntime, domains: usize) -> Result<OCamlExecutor, String> {
+        let pool = OCamlDomainPool::new(cr, domains)?;
+        Ok(OCamlExecutor {
+            backend: Arc::new(Backend::Domains(pool)),
+        })
+    }
+
+    /// Runs `f` on the OCaml runtime, returning a future that resolves to its result.
+    ///
+    /// `f` starts running even if the returned future is never polled. Panics in `f` are
+    /// propagated when the future is polled.
+    pub fn spawn<F, T>(&self, f: F) -> OCamlTask<T>
+    where
+        F: FnOnce(&mut OCamlRuntime) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(TaskState {
+            result: None,
+            waker: None,
+        }));
+        let task_state = state.clone();
+        let job: Job = Box::new(move |cr| {
+            let result = catch_unwind(AssertUnwindSafe(|| f(cr)));
+            let waker = {
+                let mut state = task_state.lock().unwrap_or_else(|e| e.into_inner());
+                state.result = Some(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+        match &*self.backend {
+            Backend::Thread(jobs) => jobs
+                .send(job)
+                .expect("OCaml executor thread is not running"),
+            Backend::Domains(pool) => pool.execute(job),
+        }
+        OCamlTask { state }
+    }
+}
+
+impl Default for OCamlExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type TaskResult<T> = Result<T, Box<dyn Any + Send>>;
+
+struct TaskState<T> {
+    result: Option<TaskResult<T>>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by [`OCamlExecutor::spawn`] that resolves to the result of the closure.
+pub struct OCamlTask<T> {
+    state: Arc<Mutex<TaskState<T>>>,
+}
+
+impl<T> Future for OCamlTask<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(panic_payload)) => {
+                drop(state);
+                resume_unwind(panic_payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::marker::PhantomData;
+
+use crate::{
+    mlvalues::{RawOCaml, UNIT},
+    rust_closure::alloc_rust_closure1,
+    BoxRoot, OCaml, OCamlRef, OCamlRuntime,
+};
+
+// Primitives behind `Gc.finalise` and `Gc.finalise_last`, not exposed by `ocaml-sys`.
+extern "C" {
+    fn caml_final_register(f: RawOCaml, v: RawOCaml) -> RawOCaml;
+    fn caml_final_register_called_without_value(f: RawOCaml, v: RawOCaml) -> RawOCaml;
+}
+
+impl OCamlRuntime {
+    /// Registers `f` to be called when the value in `value` becomes unreachable,
+    /// with the same semantics as OCaml's `Gc.finalise`.
+    ///
+    /// The value is passed to `f` rooted, so that it stays valid if `f` allocates, and `f`
+    /// may make it reachable again. Finalisers run on whatever thread holds the domain lock
+    /// when the GC notices the value is unreachable, possibly on another domain, so `f`
+    /// has to be `Send`. Panics are raised as OCaml exceptions at that point.
+    ///
+    /// Requires the `ocaml_interop_closure_call1` dispatcher to be registered from the
+    /// OCaml side:
+    ///
+    /// ```ocaml
+    /// external ocaml_interop_closure_call1 : 'f -> 'a -> 'r = "ocaml_interop_closure_call1"
+    /// let () = Callback.register "ocaml_interop_closure_call1" ocaml_interop_closure_call1
+    /// ```
+    ///
+    /// Panics if the value is not a heap-allocated block.
+    pub fn finalise<T: 'static>(
+        &mut self,
+        value: OCamlRef<T>,
+        f: impl FnOnce(&mut OCamlRuntime, OCamlRef<T>) + Send + 'static,
+    ) {
+        let mut f = Some(f);
+        let finaliser = alloc_rust_closure1::<T, ()>(self, move |cr, raw| {
+            if let Some(f) = f.take() {
+                let value: BoxRoot<T> = BoxRoot::new(OCaml {
+                    _marker: PhantomData,
+                    raw,
+                });
+                f(cr, &value);
+            }
+            UNIT
+        });
+        unsafe { register_finaliser(caml_final_register, finaliser.raw(), value) }
+    }
+
+    /// Registers `f` to be called when the value in `value` becomes unreachable,
+    /// with the same semantics as OCaml's `Gc.finalise_last`.
+    ///
+    /// Unlike [`OCamlRuntime::finalise`], the value is not passed to `f` and `f` is
+    /// called only after the value has been fully collected. See [`OCamlRuntime::finalise`]
+    /// for the OCaml side requirements.
+    ///
+    /// Panics if the value is not a heap-allocated block.
+    pub fn finalise_last<T: 'static>(
+        &mut self,
+        value: OCamlRef<T>,
+        f: impl FnOnce(&mut OCamlRuntime) + Send + 'static,
+    ) {
+        let mut f = Some(f);
+        let finaliser = alloc_rust_closure1::<(), ()>(self, move |cr, _unit| {
+            if let Some(f) = f.take() {
+                f(cr);
+            }
+            UNIT
+        });
+        unsafe {
+            register_finaliser(
+                caml_final_register_called_without_value,
+                finaliser.raw(),
+                value,
+            )
+        }
+    }
+}
+
+unsafe fn register_finaliser<T>(
+    register: unsafe extern "C" fn(RawOCaml, RawOCaml) -> RawOCaml,
+    finaliser: RawOCaml,
+    value: OCamlRef<T>,
+) {
+    // Allocating the finaliser may have moved the value, so it is read only now.
+    let raw = value.get_raw();
+    // The primitive raises `Invalid_argument` for immediate values, check beforehand
+    // so that this results in a panic instead.
+    if !ocaml_sys::is_block(raw) {
+        panic!("Finalisers can only be attached to heap-allocated OCaml values");
+    }
+    register(finaliser, raw);
+}
@@ -0,0 +1,575 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::{
+    ffi::CString,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use ocaml_sys::{
+    caml_alloc_tuple, caml_callback3_exn, caml_copy_double, caml_gc_compaction, caml_gc_full_major,
+    caml_gc_major, caml_gc_minor, caml_named_value, caml_sys_double_val, field, int_val,
+    store_field, val_int, wosize_val,
+};
+
+use crate::{
+    boxroot::BoxRoot,
+    mlvalues::{RawOCaml, NONE, UNIT},
+    runtime::internal::recover_runtime_handle,
+    rust_closure::alloc_rust_closure1,
+    OCaml, OCamlRef, OCamlRuntime, SendableRoot,
+};
+
+// GC primitives behind the `Gc` module, not exposed by `ocaml-sys`.
+extern "C" {
+    fn caml_gc_stat(unit: RawOCaml) -> RawOCaml;
+    fn caml_gc_quick_stat(unit: RawOCaml) -> RawOCaml;
+    fn caml_gc_counters(unit: RawOCaml) -> RawOCaml;
+    fn caml_gc_minor_words(unit: RawOCaml) -> RawOCaml;
+    fn caml_gc_get(unit: RawOCaml) -> RawOCaml;
+    fn caml_gc_set(control: RawOCaml) -> RawOCaml;
+    fn caml_memprof_stop(unit: RawOCaml) -> RawOCaml;
+    fn caml_memprof_discard(profile: RawOCaml) -> RawOCaml;
+    fn caml_ml_domain_id(unit: RawOCaml) -> RawOCaml;
+    fn caml_raw_backtrace_length(backtrace: RawOCaml) -> RawOCaml;
+}
+
+/// GC statistics, mirrors OCaml's `Gc.stat` record.
+///
+/// Some of these counters are not maintained by the OCaml 5 runtime and are always `0`
+/// (`heap_chunks`, `live_words`, `live_blocks`, `free_words`, `free_blocks`,
+/// `largest_free` and `fragments`). They are kept for parity with `Gc.stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OCamlGcStat {
+    /// Number of words allocated in the minor heap since the program was started.
+    pub minor_words: f64,
+    /// Number of words allocated in the minor heap that survived a minor collection.
+    pub promoted_words: f64,
+    /// Number of words allocated in the major heap, including the promoted words.
+    pub major_words: f64,
+    /// Number of minor collections since the program was started.
+    pub minor_collections: i64,
+    /// Number of major collection cycles completed since the program was started.
+    pub major_collections: i64,
+    /// Total size of the major heap, in words.
+    pub heap_words: i64,
+    /// Number of contiguous pieces of memory that make up the major heap.
+    pub heap_chunks: i64,
+    /// Number of words of live data in the major heap, including the header words.
+    pub live_words: i64,
+    /// Number of live blocks in the major heap.
+    pub live_blocks: i64,
+    /// Number of words in the free list.
+    pub free_words: i64,
+    /// Number of blocks in the free list.
+    pub free_blocks: i64,
+    /// Size (in words) of the largest block in the free list.
+    pub largest_free: i64,
+    /// Number of wasted words due to fragmentation.
+    pub fragments: i64,
+    /// Number of heap compactions since the program was started.
+    pub compactions: i64,
+    /// Maximum size reached by the major heap, in words.
+    pub top_heap_words: i64,
+    /// Current size of the stack, in words.
+    pub stack_size: i64,
+    /// Number of forced full major collections completed since the program was started.
+    pub forced_major_collections: i64,
+}
+
+/// Allocation counters, as returned by OCaml's `Gc.counters`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct OCamlGcCounters {
+    /// Number of words allocated in the minor heap, the first component of `Gc.counters`.
+    pub minor_words: f64,
+    /// Number of words promoted from the minor to the major heap, the second component.
+    pub promoted_words: f64,
+    /// Number of words allocated in the major heap, including the promoted words, the third
+    /// component.
+    pub major_words: f64,
+}
+
+/// GC parameters, mirrors OCaml's `Gc.control` record.
+///
+/// Obtain the current values with [`OCamlRuntime::gc_get`], modify the fields of
+/// interest and apply them with [`OCamlRuntime::gc_set`]. Fields ignored by the
+/// OCaml 5 runtime are kept for parity with `Gc.control`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OCamlGcControl {
+    /// Size of the minor heap, in words.
+    pub minor_heap_size: i64,
+    /// Ignored by OCaml 5.
+    pub major_heap_increment: i64,
+    /// Major GC speed, as a percentage of live data (`o` in `OCAMLRUNPARAM`).
+    pub space_overhead: i64,
+    /// Bitmask controlling GC messages on stderr (`v` in `OCAMLRUNPARAM`).
+    pub verbose: i64,
+    /// Ignored by OCaml 5.
+    pub max_overhead: i64,
+    /// Maximum size of the fiber stacks, in words (`l` in `OCAMLRUNPARAM`).
+    pub stack_limit: i64,
+    /// Ignored by OCaml 5.
+    pub allocation_policy: i64,
+    /// Ignored by OCaml 5.
+    pub window_size: i64,
+    /// Target ratio of floating garbage to major heap size for out-of-heap memory
+    /// held by custom values in the major heap.
+    pub custom_major_ratio: i64,
+    /// Bound on floating garbage for out-of-heap memory held by custom values in
+    /// the minor heap.
+    pub custom_minor_ratio: i64,
+    /// Maximum amount of out-of-heap memory for each custom value allocated in the
+    /// minor heap.
+    pub custom_minor_max_size: i64,
+}
+
+const GC_CONTROL_FIELDS: usize = 11;
+
+/// Handle to an alarm created with [`OCamlRuntime::gc_create_alarm`].
+///
+/// The alarm is deleted when this handle is dropped.
+pub struct OCamlGcAlarm {
+    state: Arc<AlarmState>,
+}
+
+type AlarmCallback = Box<dyn FnMut(&mut OCamlRuntime) + Send>;
+
+struct AlarmState {
+    active: AtomicBool,
+    callback: Mutex<AlarmCallback>,
+}
+
+impl OCamlGcAlarm {
+    /// Deletes the alarm, the callback will not be called anymore.
+    pub fn delete(self) {}
+}
+
+impl Drop for OCamlGcAlarm {
+    fn drop(&mut self) {
+        self.state.active.store(false, Ordering::Release);
+    }
+}
+
+/// Origin of an allocation sampled by [`OCamlRuntime::memprof_start`],
+/// mirrors OCaml's `Gc.Memprof.allocation_source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OCamlAllocationSource {
+    Normal,
+    Marshal,
+    Custom,
+    MapFile,
+    /// A source unknown to this version of `ocaml-interop`.
+    Unknown,
+}
+
+/// An allocation sampled by [`OCamlRuntime::memprof_start`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OCamlAllocationSample {
+    /// Number of samples in this block, at least 1.
+    pub n_samples: i64,
+    /// Size of the allocated block, in words, excluding the header.
+    pub size: i64,
+    /// Origin of the allocation.
+    pub source: OCamlAllocationSource,
+    /// `true` if the block was allocated directly in the major heap.
+    pub major: bool,
+    /// Length of the callstack captured for this allocation.
+    pub callstack_length: usize,
+}
+
+const MEMPROF_START_NAME: &str = "ocaml_interop_memprof_start";
+
+/// Handle to the memprof profile started with [`OCamlRuntime::memprof_start`].
+///
+/// Use [`OCamlMemprof::stop`] to stop sampling. The handle can be dropped without holding
+/// the domain lock, so dropping it only schedules the profile to be stopped the next time
+/// the domain that started it enters the runtime through [`OCamlRuntime::with_domain_lock`]
+/// or starts another profile. Profiles already stopped from the OCaml side are only
+/// discarded.
+pub struct OCamlMemprof {
+    profile: Option<MemprofProfile>,
+}
+
+struct MemprofProfile {
+    profile: SendableRoot<()>,
+    domain_id: isize,
+}
+
+// Profiles dropped without being stopped, see `release_pending_profiles`.
+static PENDING_PROFILES: Mutex<Vec<MemprofProfile>> = Mutex::new(Vec::new());
+static HAS_PENDING_PROFILES: AtomicBool = AtomicBool::new(false);
+
+// Status of a profile, stored in its first field, see `runtime/memprof.c`
+const MEMPROF_STATUS_SAMPLING: isize = 0;
+const MEMPROF_STATUS_STOPPED: isize = 1;
+
+fn current_domain_id() -> isize {
+    unsafe { int_val(caml_ml_domain_id(UNIT)) }
+}
+
+impl OCamlMemprof {
+    /// Stops sampling allocations.
+    ///
+    /// If the profile was started by another domain, it is stopped the next time that
+    /// domain enters the runtime, see [`OCamlMemprof`].
+    pub fn stop(mut self, cr: &mut OCamlRuntime) {
+        if let Some(profile) = self.profile.take() {
+            if !profile.release(cr) {
+                defer_profile(profile);
+            }
+        }
+    }
+}
+
+impl Drop for OCamlMemprof {
+    fn drop(&mut self) {
+        if let Some(profile) = self.profile.take() {
+            defer_profile(profile);
+        }
+    }
+}
+
+impl MemprofProfile {
+    // Stops and discards the profile. Returns `false` if it is still sampling on another
+    // domain, which is the only one that can stop it.
+    fn release(&self, cr: &OCamlRuntime) -> bool {
+        let raw = unsafe { self.profile.get(cr).raw() };
+        let status = || unsafe { int_val(*field(raw, 0)) };
+        // `Gc.Memprof.stop` and `Gc.Memprof.discard` raise when the profile is not in the
+        // expected state, which is checked first so that no exception is raised from here.
+        unsafe {
+            if status() == MEMPROF_STATUS_SAMPLING {
+                if current_domain_id() != self.domain_id {
+                    return false;
+                }
+                caml_memprof_stop(UNIT);
+            }
+            if status() == MEMPROF_STATUS_STOPPED {
+                caml_memprof_discard(raw);
+            }
+        }
+        true
+    }
+}
+
+fn defer_profile(profile: MemprofProfile) {
+    PENDING_PROFILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(profile);
+    HAS_PENDING_PROFILES.store(true, Ordering::Release);
+}
+
+/// Stops the profiles that were dropped without being stopped, if they were started by
+/// the current domain, and discards the ones already stopped.
+///
+/// Must be called with the domain lock held.
+pub(crate) fn release_pending_profiles() {
+    if !HAS_PENDING_PROFILES.load(Ordering::Relaxed) {
+        return;
+    }
+    let cr = unsafe { recover_runtime_handle() };
+    let mut pending = PENDING_PROFILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    pending.retain(|profile| !profile.release(cr));
+    HAS_PENDING_PROFILES.store(!pending.is_empty(), Ordering::Release);
+}
+
+impl OCamlRuntime {
+    /// Triggers a minor collection (`Gc.minor`).
+    pub fn gc_minor(&mut self) {
+        unsafe { caml_gc_minor(UNIT) }
+    }
+
+    /// Triggers a minor collection and finishes the current major cycle (`Gc.major`).
+    pub fn gc_major(&mut self) {
+        unsafe { caml_gc_major(UNIT) }
+    }
+
+    /// Finishes the current major cycle and performs a complete new one (`Gc.full_major`).
+    pub fn gc_full_major(&mut self) {
+        unsafe { caml_gc_full_major(UNIT) }
+    }
+
+    /// Performs a full major collection and compacts the heap (`Gc.compact`).
+    pub fn gc_compact(&mut self) {
+        unsafe { caml_gc_compaction(UNIT) }
+    }
+
+    /// Returns the current GC statistics (`Gc.stat`).
+    ///
+    /// This may be slow, prefer [`OCamlRuntime::gc_quick_stat`] for frequent sampling.
+    pub fn gc_stat(&mut self) -> OCamlGcStat {
+        unsafe { read_gc_stat(caml_gc_stat(UNIT)) }
+    }
+
+    /// Returns the current GC statistics without computing the ones that would
+    /// require a heap traversal (`Gc.quick_stat`).
+    pub fn gc_quick_stat(&mut self) -> OCamlGcStat {
+        unsafe { read_gc_stat(caml_gc_quick_stat(UNIT)) }
+    }
+
+    /// Returns the allocation counters (`Gc.counters`).
+    pub fn gc_counters(&mut self) -> OCamlGcCounters {
+        unsafe {
+            let counters = caml_gc_counters(UNIT);
+            OCamlGcCounters {
+                minor_words: float_field(counters, 0),
+                promoted_words: float_field(counters, 1),
+                major_words: float_field(counters, 2),
+            }
+        }
+    }
+
+    /// Number of words allocated in the minor heap by this domain (`Gc.minor_words`).
+    pub fn gc_minor_words(&mut self) -> f64 {
+        unsafe { caml_sys_double_val(caml_gc_minor_words(UNIT)) }
+    }
+
+    /// Returns the current GC parameters (`Gc.get`).
+    pub fn gc_get(&mut self) -> OCamlGcControl {
+        unsafe {
+            let control = caml_gc_get(UNIT);
+            OCamlGcControl {
+                minor_heap_size: int_field(control, 0),
+                major_heap_increment: int_field(control, 1),
+                space_overhead: int_field(control, 2),
+                verbose: int_field(control, 3),
+                max_overhead: int_field(control, 4),
+                stack_limit: int_field(control, 5),
+                allocation_policy: int_field(control, 6),
+                window_size: int_field(control, 7),
+                custom_major_ratio: int_field(control, 8),
+                custom_minor_ratio: int_field(control, 9),
+                custom_minor_max_size: int_field(control, 10),
+            }
+        }
+    }
+
+    /// Changes the GC parameters (`Gc.set`).
+    ///
+    /// Changing `minor_heap_size` triggers a minor collection.
+    pub fn gc_set(&mut self, control: &OCamlGcControl) {
+        let fields = [
+            control.minor_heap_size,
+            control.major_heap_increment,
+            control.space_overhead,
+            control.verbose,
+            control.max_overhead,
+            control.stack_limit,
+            control.allocation_policy,
+            control.window_size,
+            control.custom_major_ratio,
+            control.custom_minor_ratio,
+            control.custom_minor_max_size,
+        ];
+        unsafe {
+            let record = caml_alloc_tuple(GC_CONTROL_FIELDS);
+            for (i, value) in fields.into_iter().enumerate() {
+                store_field(record, i, val_int(value as isize));
+            }
+            caml_gc_set(record);
+        }
+    }
+
+    /// Sets the major GC speed (`space_overhead`), keeping the other GC parameters.
+    pub fn gc_set_space_overhead(&mut self, space_overhead: i64) {
+        let control = OCamlGcControl {
+            space_overhead,
+            ..self.gc_get()
+        };
+        self.gc_set(&control);
+    }
+
+    /// Sets the size of the minor heap in words, keeping the other GC parameters.
+    pub fn gc_set_minor_heap_size(&mut self, minor_heap_size: i64) {
+        let control = OCamlGcControl {
+            minor_heap_size,
+            ..self.gc_get()
+        };
+        self.gc_set(&control);
+    }
+
+    /// Calls `f` at the end of each major GC cycle, like OCaml's `Gc.create_alarm`.
+    ///
+    /// The alarm is built on top of [`OCamlRuntime::finalise`], and so has the same OCaml
+    /// side requirements and the same `Send` bound.
+    pub fn gc_create_alarm(
+        &mut self,
+        f: impl FnMut(&mut OCamlRuntime) + Send + 'static,
+    ) -> OCamlGcAlarm {
+        let state = Arc::new(AlarmState {
+            active: AtomicBool::new(true),
+            callback: Mutex::new(Box::new(f)),
+        });
+        let token: BoxRoot<()> = BoxRoot::new(unsafe { OCaml::new(self, caml_alloc_tuple(1)) });
+        arm_alarm(self, &token, state.clone());
+        OCamlGcAlarm { state }
+    }
+
+    /// Starts sampling allocations, like OCaml's `Gc.Memprof.start`.
+    ///
+    /// `sampling_rate` is the probability for each allocated word to be sampled, and
+    /// `callstack_size` the maximum length of the callstacks captured for each sample.
+    /// `f` is called for each sampled allocation. Sampling continues until the returned
+    /// [`OCamlMemprof`] handle is stopped or dropped.
+    ///
+    /// `f` has to be `Send`, like the closures passed to [`OCamlRuntime::finalise`].
+    ///
+    /// Requires the same OCaml side setup as [`OCamlRuntime::finalise`], and a function
+    /// starting the profile to be registered from the OCaml side, so that the exceptions
+    /// raised by `Gc.Memprof.start` don't go through Rust code:
+    ///
+    /// ```ocaml
+    /// let () =
+    ///   Callback.register "ocaml_interop_memprof_start"
+    ///     (fun sampling_rate callstack_size tracker ->
+    ///       Gc.Memprof.start ~sampling_rate ~callstack_size tracker)
+    /// ```
+    ///
+    /// Returns `Err(String)` if the function is not registered, or if the profile couldn't
+    /// be started (for example when a profile is already running on the current domain).
+    ///
+    /// Panics if `sampling_rate` is not between `0.0` and `1.0`.
+    pub fn memprof_start(
+        &mut self,
+        sampling_rate: f64,
+        callstack_size: usize,
+        f: impl FnMut(&mut OCamlRuntime, &OCamlAllocationSample) + Send + 'static,
+    ) -> Result<OCamlMemprof, String> {
+        assert!(
+            (0.0..=1.0).contains(&sampling_rate),
+            "Memprof sampling rate must be between 0.0 and 1.0, got {sampling_rate}"
+        );
+        release_pending_profiles();
+        let start = unsafe {
+            let name = CString::new(MEMPROF_START_NAME).unwrap();
+            caml_named_value(name.as_ptr())
+        };
+        if start.is_null() {
+            return Err(format!(
+                "OCaml closure with name '{MEMPROF_START_NAME}' not registered"
+            ));
+        }
+        let callback = Arc::new(Mutex::new(f));
+        let alloc_minor = self.memprof_callback(callback.clone(), false);
+        let alloc_major = self.memprof_callback(callback, true);
+        // Allocations are never tracked after being sampled, so promotion and
+        // deallocation callbacks are never called.
+        let untracked = BoxRoot::new(alloc_rust_closure1::<(), ()>(self, |_, _| UNIT));
+        unsafe {
+            let tracker = caml_alloc_tuple(5);
+            store_field(tracker, 0, alloc_minor.get_raw());
+            store_field(tracker, 1, alloc_major.get_raw());
+            store_field(tracker, 2, untracked.get_raw());
+            store_field(tracker, 3, untracked.get_raw());
+            store_field(tracker, 4, untracked.get_raw());
+            let tracker = BoxRoot::<()>::new(OCaml::new(self, tracker));
+            let sampling_rate = caml_copy_double(sampling_rate);
+            let result = caml_callback3_exn(
+                *start,
+                sampling_rate,
+                val_int(callstack_size as isize),
+                tracker.get_raw(),
+            );
+            if let Some(exception) = OCaml::of_exception_result(self, result) {
+                return Err(match exception.message() {
+                    Some(message) => format!("Failed to start memprof profile: {message}"),
+                    None => "Failed to start memprof profile".to_string(),
+                });
+            }
+            Ok(OCamlMemprof {
+                profile: Some(MemprofProfile {
+                    profile: SendableRoot::new(OCaml::new(self, result)),
+                    domain_id: current_domain_id(),
+                }),
+            })
+        }
+    }
+
+    fn memprof_callback<F>(&mut self, callback: Arc<Mutex<F>>, major: bool) -> BoxRoot<fn(()) -> ()>
+    where
+        F: FnMut(&mut OCamlRuntime, &OCamlAllocationSample) + Send + 'static,
+    {
+        BoxRoot::new(alloc_rust_closure1::<(), ()>(
+            self,
+            move |cr, allocation| {
+                let sample = unsafe { read_allocation_sample(allocation, major) };
+                // Memprof callbacks are not run while another one is running on the domain
+                let mut callback = callback.lock().unwrap_or_else(|e| e.into_inner());
+                (callback)(cr, &sample);
+                // Don't track the allocated block
+                NONE
+            },
+        ))
+    }
+}
+
+fn arm_alarm(cr: &mut OCamlRuntime, token: OCamlRef<()>, state: Arc<AlarmState>) {
+    cr.finalise(token, move |cr, token| {
+        if state.active.load(Ordering::Acquire) {
+            arm_alarm(cr, token, state.clone());
+            let mut callback = state.callback.lock().unwrap_or_else(|e| e.into_inner());
+            (callback)(cr);
+        }
+    });
+}
+
+unsafe fn read_allocation_sample(allocation: RawOCaml, major: bool) -> OCamlAllocationSample {
+    let source = match int_val(*field(allocation, 2)) {
+        0 => OCamlAllocationSource::Normal,
+        1 => OCamlAllocationSource::Marshal,
+        2 => OCamlAllocationSource::Custom,
+        3 => OCamlAllocationSource::MapFile,
+        _ => OCamlAllocationSource::Unknown,
+    };
+    OCamlAllocationSample {
+        n_samples: int_field(allocation, 0),
+        size: int_field(allocation, 1),
+        source,
+        major,
+        callstack_length: int_val(caml_raw_backtrace_length(*field(allocation, 3))) as usize,
+    }
+}
+
+unsafe fn int_field(block: RawOCaml, index: usize) -> i64 {
+    int_val(*field(block, index)) as i64
+}
+
+unsafe fn float_field(block: RawOCaml, index: usize) -> f64 {
+    caml_sys_double_val(*field(block, index))
+}
+
+unsafe fn read_gc_stat(stat: RawOCaml) -> OCamlGcStat {
+    // Guard against older or newer layouts of `Gc.stat`
+    let int_field_or_zero = |index: usize| {
+        if index < wosize_val(stat) {
+            int_field(stat, index)
+        } else {
+            0
+        }
+    };
+    OCamlGcStat {
+        minor_words: float_field(stat, 0),
+        promoted_words: float_field(stat, 1),
+        major_words: float_field(stat, 2),
+        minor_collections: int_field_or_zero(3),
+        major_collections: int_field_or_zero(4),
+        heap_words: int_field_or_zero(5),
+        heap_chunks: int_field_or_zero(6),
+        live_words: int_field_or_zero(7),
+        live_blocks: int_field_or_zero(8),
+        free_words: int_field_or_zero(9),
+        free_blocks: int_field_or_zero(10),
+        largest_free: int_field_or_zero(11),
+        fragments: int_field_or_zero(12),
+        compactions: int_field_or_zero(13),
+        top_heap_words: int_field_or_zero(14),
+        stack_size: int_field_or_zero(15),
+        forced_major_collections: int_field_or_zero(16),
+    }
+}
@@ -0,0 +1,283 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::{
+    marker::PhantomData,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use ocaml_sys::{
+    caml_modify_generational_global_root, caml_register_generational_global_root,
+    caml_remove_generational_global_root,
+};
+
+use crate::{
+    memory::OCamlCell, mlvalues::RawOCaml, runtime::domain_lock_held, OCaml, OCamlRef, OCamlRuntime,
+};
+
+// Heap allocated slot registered as a generational global root.
+struct GlobalRootSlot {
+    slot: *mut RawOCaml,
+}
+
+// The slot is only read or written with the domain lock held, and is sent to the
+// pending removals queue when released from threads that don't hold it.
+unsafe impl Send for GlobalRootSlot {}
+unsafe impl Sync for GlobalRootSlot {}
+
+impl GlobalRootSlot {
+    fn new(raw: RawOCaml) -> Self {
+        let slot = Box::into_raw(Box::new(raw));
+        unsafe { caml_register_generational_global_root(slot) };
+        GlobalRootSlot { slot }
+    }
+
+    unsafe fn remove(self) {
+        caml_remove_generational_global_root(self.slot);
+        drop(Box::from_raw(self.slot));
+    }
+}
+
+// Removing a global root requires holding a domain lock. Roots dropped from threads
+// that are not known to hold it are queued here and removed the next time a domain
+// lock is acquired through `OCamlRuntime::with_domain_lock`, a global root is created,
+// or a `LazyGlobalRoot` is accessed.
+static PENDING_REMOVALS: Mutex<Vec<GlobalRootSlot>> = Mutex::new(Vec::new());
+static HAS_PENDING_REMOVALS: AtomicBool = AtomicBool::new(false);
+
+fn release_slot(slot: GlobalRootSlot) {
+    if domain_lock_held() {
+        unsafe { slot.remove() }
+    } else {
+        let mut pending = PENDING_REMOVALS.lock().unwrap_or_else(|e| e.into_inner());
+        pending.push(slot);
+        HAS_PENDING_REMOVALS.store(true, Ordering::Release);
+    }
+}
+
+/// Removes the roots dropped from threads that didn't hold the domain lock.
+///
+/// Must be called with the domain lock held.
+pub(crate) fn remove_pending_roots() {
+    if !HAS_PENDING_REMOVALS.load(Ordering::Relaxed)
+        || !HAS_PENDING_REMOVALS.swap(false, Ordering::Acquire)
+    {
+        return;
+    }
+    let pending = std::mem::take(&mut *PENDING_REMOVALS.lock().unwrap_or_else(|e| e.into_inner()));
+    for slot in pending {
+        unsafe { slot.remove() }
+    }
+}
+
+/// A rooted OCaml value that can be sent to and shared with other threads.
+///
+/// Unlike [`BoxRoot`](crate::BoxRoot)`<T>`, a `SendableRoot<T>` is `Send + Sync`, so it can
+/// be passed through channels or shared by worker threads. The value can only be accessed
+/// with an [`OCamlRuntime`] handle, on a thread that holds the domain lock.
+///
+/// It is backed by a generational global root. Dropping it on a thread that isn't known to
+/// hold the domain lock is safe: the root is removed the next time a thread acquires the
+/// domain lock through [`OCamlRuntime::with_domain_lock`] or creates another global root,
+/// and until then the value is kept alive.
+///
+/// ```rust,no_run
+/// use ocaml_interop::{OCamlRuntime, SendableRoot, ToOCaml};
+///
+/// let (sender, receiver) = std::sync::mpsc::channel();
+/// std::thread::spawn(move || {
+///     OCamlRuntime::with_domain_lock(|cr| {
+///         let value = "computed in a worker".to_ocaml(cr);
+///         sender.send(SendableRoot::<String>::new(value)).unwrap();
+///     })
+/// });
+/// let root = receiver.recv().unwrap();
+/// let result: String = OCamlRuntime::with_domain_lock(|cr| root.get(cr).to_rust());
+/// ```
+pub struct SendableRoot<T: 'static> {
+    slot: Option<GlobalRootSlot>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> SendableRoot<T> {
+    /// Creates a new root from an [`OCaml`]`<T>` value.
+    pub fn new(val: OCaml<T>) -> SendableRoot<T> {
+        remove_pending_roots();
+        SendableRoot {
+            slot: Some(GlobalRootSlot::new(unsafe { val.raw() })),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the value stored in this root as an [`OCaml`]`<T>`.
+    pub fn get<'a>(&self, cr: &'a OCamlRuntime) -> OCaml<'a, T> {
+        unsafe { OCaml::new(cr, *self.slot_ptr()) }
+    }
+
+    /// Gets a reference to the value stored in this root, to be passed to functions
+    /// expecting an [`OCamlRef`]`<T>`.
+    pub fn get_ref<'a>(&'a self, _cr: &'a OCamlRuntime) -> OCamlRef<'a, T> {
+        unsafe { OCamlCell::create_ref(self.slot_ptr()) }
+    }
+
+    /// Roots the OCaml value `val`, replacing the current one, and returns an
+    /// [`OCamlRef`]`<T>` to it.
+    pub fn keep<'tmp>(&'tmp mut self, val: OCaml<T>) -> OCamlRef<'tmp, T> {
+        unsafe {
+            caml_modify_generational_global_root(self.slot_ptr(), val.raw());
+            OCamlCell::create_ref(self.slot_ptr())
+        }
+    }
+
+    fn slot_ptr(&self) -> *mut RawOCaml {
+        self.slot.as_ref().unwrap().slot
+    }
+}
+
+impl<T> Drop for SendableRoot<T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            release_slot(slot);
+        }
+    }
+}
+
+/// `GlobalRoot<T>` is a container for a rooted [`OCaml`]`<T>` value, backed by a
+/// generational global root.
+///
+/// It is an alternative to [`BoxRoot`](crate::BoxRoot)`<T>` that doesn't depend on the
+/// boxroot library, and that is better suited for values that stay rooted for a long
+/// time. Creating and deleting global roots is more expensive than boxroots, so prefer
+/// [`BoxRoot`](crate::BoxRoot) for short-lived roots.
+///
+/// For roots stored in `static`s, see [`LazyGlobalRoot`].
+pub struct GlobalRoot<T: 'static> {
+    slot: Option<GlobalRootSlot>,
+    _marker: PhantomData<*const T>,
+}
+
+impl<T> GlobalRoot<T> {
+    /// Creates a new root from an [`OCaml`]`<T>` value.
+    pub fn new(val: OCaml<T>) -> GlobalRoot<T> {
+        remove_pending_roots();
+        GlobalRoot {
+            slot: Some(GlobalRootSlot::new(unsafe { val.raw() })),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the value stored in this root as an [`OCaml`]`<T>`.
+    pub fn get<'a>(&self, cr: &'a OCamlRuntime) -> OCaml<'a, T> {
+        unsafe { OCaml::new(cr, *self.slot_ptr()) }
+    }
+
+    /// Roots the OCaml value `val`, returning an [`OCamlRef`]`<T>`.
+    pub fn keep<'tmp>(&'tmp mut self, val: OCaml<T>) -> OCamlRef<'tmp, T> {
+        unsafe {
+            caml_modify_generational_global_root(self.slot_ptr(), val.raw());
+            OCamlCell::create_ref(self.slot_ptr())
+        }
+    }
+
+    fn slot_ptr(&self) -> *mut RawOCaml {
+        self.slot.as_ref().unwrap().slot
+    }
+}
+
+impl<T> Drop for GlobalRoot<T> {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot.take() {
+            release_slot(slot);
+        }
+    }
+}
+
+impl<T> Deref for GlobalRoot<T> {
+    type Target = OCamlCell<T>;
+
+    fn deref(&self) -> OCamlRef<'_, T> {
+        unsafe { OCamlCell::create_ref(self.slot_ptr()) }
+    }
+}
+
+/// A global root that can be stored in a `static`, initialized the first time it is
+/// accessed.
+///
+/// ```rust,no_run
+/// use ocaml_interop::{LazyGlobalRoot, OCaml, OCamlList, OCamlRuntime, ToOCaml};
+///
+/// static DEFAULT_PATHS: LazyGlobalRoot<OCamlList<String>> =
+///     LazyGlobalRoot::new(|cr| vec!["/etc".to_string(), "/usr/etc".to_string()].to_ocaml(cr));
+///
+/// OCamlRuntime::with_domain_lock(|cr| {
+///     let paths: Vec<String> = DEFAULT_PATHS.get(cr).to_rust();
+/// });
+/// ```
+///
+/// The value stays rooted for the rest of the program. If several domains access the root
+/// for the first time at once, `init` may run on more than one of them, and only one of the
+/// values is kept.
+pub struct LazyGlobalRoot<T: 'static> {
+    init: fn(&mut OCamlRuntime) -> OCaml<T>,
+    slot: OnceLock<GlobalRootSlot>,
+}
+
+impl<T> LazyGlobalRoot<T> {
+    /// Creates a root that will be initialized with the value returned by `init`.
+    pub const fn new(init: fn(&mut OCamlRuntime) -> OCaml<T>) -> LazyGlobalRoot<T> {
+        LazyGlobalRoot {
+            init,
+            slot: OnceLock::new(),
+        }
+    }
+
+    /// Gets the value stored in this root as an [`OCaml`]`<T>`, initializing it
+    /// if it wasn't initialized yet.
+    pub fn get<'a>(&self, cr: &'a mut OCamlRuntime) -> OCaml<'a, T> {
+        let slot = self.force(cr);
+        unsafe { OCaml::new(cr, *slot) }
+    }
+
+    /// Gets a reference to the value stored in this root, initializing it if it
+    /// wasn't initialized yet.
+    pub fn get_ref<'a>(&'a self, cr: &mut OCamlRuntime) -> OCamlRef<'a, T> {
+        unsafe { OCamlCell::create_ref(self.force(cr)) }
+    }
+
+    fn force(&self, cr: &mut OCamlRuntime) -> *mut RawOCaml {
+        remove_pending_roots();
+        if let Some(slot) = self.slot.get() {
+            return slot.slot;
+        }
+        // `init` runs outside of the `OnceLock`, so that other domains accessing the root
+        // meanwhile don't block while holding their domain lock. If one of them wins the
+        // race, the value computed here is discarded.
+        let slot = GlobalRootSlot::new(unsafe { (self.init)(cr).raw() });
+        if let Err(slot) = self.slot.set(slot) {
+            unsafe { slot.remove() }
+        }
+        self.slot.get().unwrap().slot
+    }
+}
+
+#[cfg(test)]
+mod global_root_assertions {
+    use super::*;
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+    assert_not_impl_any!(GlobalRoot<()>: Send, Sync);
+    assert_impl_all!(LazyGlobalRoot<()>: Send, Sync);
+}
+
+#[cfg(test)]
+mod sendable_root_assertions {
+    use super::*;
+    use static_assertions::assert_impl_all;
+
+    assert_impl_all!(SendableRoot<()>: Send, Sync);
+    assert_impl_all!(SendableRoot<String>: Send, Sync);
+}
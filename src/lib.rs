@@ -113,23 +113,48 @@
 //! - [ocaml-rs](https://github.com/zshipko/ocaml-rs), another OCaml<->Rust FFI library.
 
 mod boxroot;
+mod callback;
 mod closure;
 mod conv;
 mod describe;
+mod domain;
 mod error;
+#[cfg(feature = "async")]
+mod executor;
+mod finalise;
+mod gc;
+mod global_root;
+mod local_roots;
+#[cfg(feature = "lwt")]
+mod lwt;
 mod macros;
 mod memory;
 mod mlvalues;
 mod runtime;
+mod rust_closure;
 #[doc = include_str!("../docs/README.md")]
 pub mod user_guides;
 mod value;
+mod weak;
 
 pub use crate::boxroot::BoxRoot;
+pub use crate::callback::OCamlCallback;
 
 pub use crate::closure::{OCamlFn1, OCamlFn2, OCamlFn3, OCamlFn4, OCamlFn5};
 pub use crate::conv::{DefaultOCamlMapping, DefaultRustMapping, FromOCaml, ToOCaml};
 pub use crate::describe::OCamlDescriber;
+pub use crate::domain::{OCamlDomain, OCamlDomainPool};
+pub use crate::error::{OCamlDomainLockTimeout, OCamlExceptionError};
+#[cfg(feature = "async")]
+pub use crate::executor::{OCamlExecutor, OCamlTask};
+pub use crate::gc::{
+    OCamlAllocationSample, OCamlAllocationSource, OCamlGcAlarm, OCamlGcControl, OCamlGcCounters,
+    OCamlGcStat, OCamlMemprof,
+};
+pub use crate::global_root::{GlobalRoot, LazyGlobalRoot, SendableRoot};
+pub use crate::local_roots::{LocalRoot, LocalRoots};
+#[cfg(feature = "lwt")]
+pub use crate::lwt::{LwtFuture, LwtPromise, LwtResolver};
 pub use crate::memory::alloc_cons as cons;
 pub use crate::memory::OCamlRef;
 pub use crate::memory::{alloc_error, alloc_ok};
@@ -137,8 +162,11 @@ pub use crate::mlvalues::{
     bigarray, DynBox, OCamlBytes, OCamlException, OCamlFloat, OCamlFloatArray, OCamlInt,
     OCamlInt32, OCamlInt64, OCamlList, OCamlUniformArray, RawOCaml,
 };
-pub use crate::runtime::{OCamlRuntime, OCamlRuntimeStartupGuard};
+pub use crate::runtime::{
+    OCamlDomainLockStats, OCamlRuntime, OCamlRuntimeBuilder, OCamlRuntimeStartupGuard,
+};
 pub use crate::value::{OCaml, OCamlParam, RefOrRooted};
+pub use crate::weak::{OCamlWeak, OCamlWeakCache};
 
 /// Exports a Rust function to OCaml.
 ///
@@ -245,7 +273,7 @@ pub use crate::value::{OCaml, OCamlParam, RefOrRooted};
 /// [`BoxRoot<T>`]: BoxRoot
 /// [`&mut OCamlRuntime`]: OCamlRuntime
 /// [`&OCamlRuntime`]: OCamlRuntime
-pub use ocaml_interop_derive::{export, FromOCaml, OCamlDescriber, ToOCaml};
+pub use ocaml_interop_derive::{export, FromOCaml, OCamlAccessors, OCamlDescriber, ToOCaml};
 
 #[doc(hidden)]
 pub mod internal {
@@ -289,6 +317,35 @@ pub mod internal {
         unsafe { ocaml_sys::caml_copy_double(val) }
     }
 
+    /// Allocates a record of `size` unboxed floats (`Double_array_tag`).
+    ///
+    /// # Safety
+    ///
+    /// Must be called while holding the domain lock, and all the fields must be
+    /// initialized with [`store_double_field`] before the next allocation.
+    pub unsafe fn alloc_float_record(size: usize) -> super::RawOCaml {
+        let wosize = size * std::mem::size_of::<f64>() / std::mem::size_of::<super::RawOCaml>();
+        caml_alloc(wosize, tag::DOUBLE_ARRAY)
+    }
+
+    /// Stores `val` in the field `i` of a block with `Double_array_tag`.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be a block with `Double_array_tag` and at least `i + 1` fields.
+    pub unsafe fn store_double_field(block: super::RawOCaml, i: usize, val: f64) {
+        ocaml_sys::caml_sys_store_double_field(block, i, val)
+    }
+
+    /// Reads the field `i` of a block with `Double_array_tag`.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be a block with `Double_array_tag` and at least `i + 1` fields.
+    pub unsafe fn double_field(block: super::RawOCaml, i: usize) -> f64 {
+        ocaml_sys::caml_sys_double_field(block, i)
+    }
+
     pub fn make_ocaml_bool(val: bool) -> super::RawOCaml {
         unsafe { ocaml_sys::val_int(val as isize) }
     }
@@ -297,6 +354,76 @@ pub mod internal {
         unsafe { ocaml_sys::val_int(val) }
     }
 
+    /// The extension constructor registered from the OCaml side under `name`, which
+    /// identifies a constructor of an extensible variant or an exception.
+    ///
+    /// Meant to be stored in a `static`: the registered value is looked up the first time it
+    /// is accessed, and the location of the named value is reused afterwards.
+    ///
+    /// Example OCaml registration:
+    /// ```ocaml
+    /// exception Crashed of string
+    /// let () = Callback.register_exception "plugin_error.Crashed" (Crashed "")
+    ///
+    /// type event = ..
+    /// type event += Started of string
+    /// let () = Callback.register "event.Started" [%extension_constructor Started]
+    /// ```
+    pub struct ExtensionConstructor {
+        name: &'static str,
+        // Address of the named value, which stays registered for the rest of the program
+        named_value: OnceLock<usize>,
+    }
+
+    impl ExtensionConstructor {
+        pub const fn new(name: &'static str) -> Self {
+            ExtensionConstructor {
+                name,
+                named_value: OnceLock::new(),
+            }
+        }
+
+        /// Returns the extension constructor.
+        ///
+        /// # Panics
+        /// Panics if no value is registered under the name of the constructor.
+        ///
+        /// # Safety
+        /// The OCaml runtime must be initialized, and the current thread must hold the domain lock.
+        pub unsafe fn get(&self) -> super::RawOCaml {
+            let named_value = *self.named_value.get_or_init(|| {
+                let c_name =
+                    CString::new(self.name).expect("constructor names can't contain null bytes");
+                let constructor_ptr = ocaml_sys::caml_named_value(c_name.as_ptr());
+                if constructor_ptr.is_null() {
+                    panic!(
+                        "Extension constructor '{name}' is not registered, register it from OCaml \
+                         with `Callback.register_exception \"{name}\" (Exn ...)` for exceptions, \
+                         or `Callback.register \"{name}\" [%extension_constructor Constructor]` \
+                         for extensible variants",
+                        name = self.name
+                    );
+                }
+                constructor_ptr as usize
+            });
+            *(named_value as *const super::RawOCaml)
+        }
+    }
+
+    /// Returns the extension constructor of `val`, a value of an extensible variant or an
+    /// exception: the value itself for constructors without arguments, and its first field
+    /// otherwise.
+    ///
+    /// # Safety
+    /// `val` must be a value of an extensible variant or an exception.
+    pub unsafe fn extension_constructor_of(val: super::RawOCaml) -> super::RawOCaml {
+        if crate::mlvalues::tag_val(val) == tag::OBJECT {
+            val
+        } else {
+            *ocaml_sys::field(val, 0)
+        }
+    }
+
     // Static storage for the OCaml exception constructor for Rust panics.
     // This will hold the OCaml value for an exception like `exception RustPanic of string`.
     static RUST_PANIC_EXCEPTION_CONSTRUCTOR: OnceLock<Option<super::RawOCaml>> = OnceLock::new();
@@ -0,0 +1,126 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::{cell::UnsafeCell, marker::PhantomData};
+
+use ocaml_sys::{local_roots, set_local_roots, CamlRootsBlock};
+
+use crate::{memory::OCamlCell, mlvalues::RawOCaml, OCaml, OCamlRef, OCamlRuntime};
+
+/// Stack storage for `N` local roots, registered with the OCaml local roots mechanism
+/// (the equivalent of `CAMLlocalN` in C).
+///
+/// Local roots are cheaper than [`BoxRoot`](crate::BoxRoot)s: opening a frame just links
+/// this storage into the list of local roots of the domain, and rooting a value is a
+/// single store. Roots are only valid until the end of the scope in which they were
+/// opened, see [`LocalRoots::scope`] and [`ocaml_frame!`](crate::ocaml_frame).
+pub struct LocalRoots<const N: usize> {
+    block: UnsafeCell<CamlRootsBlock>,
+    slots: [UnsafeCell<RawOCaml>; N],
+}
+
+impl<const N: usize> LocalRoots<N> {
+    /// Creates the storage for `N` local roots, all initialized to `()`.
+    pub fn new() -> Self {
+        LocalRoots {
+            block: UnsafeCell::new(CamlRootsBlock::default()),
+            slots: std::array::from_fn(|_| UnsafeCell::new(ocaml_sys::UNIT)),
+        }
+    }
+
+    /// Opens a frame with `N` local roots for the duration of `f`.
+    ///
+    /// ```rust,no_run
+    /// use ocaml_interop::{LocalRoots, OCamlRef, OCamlRuntime, ToOCaml};
+    ///
+    /// fn make_pair(cr: &mut OCamlRuntime) -> (String, String) {
+    ///     LocalRoots::<2>::scope(cr, |cr, [mut a, mut b]| {
+    ///         let a: OCamlRef<String> = a.keep("first".to_ocaml(cr));
+    ///         let b: OCamlRef<String> = b.keep("second".to_ocaml(cr));
+    ///         (a.to_rust(cr), b.to_rust(cr))
+    ///     })
+    /// }
+    /// ```
+    pub fn scope<F, R>(cr: &mut OCamlRuntime, f: F) -> R
+    where
+        F: for<'r> FnOnce(&mut OCamlRuntime, [LocalRoot<'r>; N]) -> R,
+    {
+        let mut storage = LocalRoots::<N>::new();
+        let (_frame, roots) = unsafe { storage.open(cr) };
+        f(cr, roots)
+    }
+
+    /// Links this storage into the local roots of the current domain, returning
+    /// the roots and a guard that unlinks them when dropped.
+    ///
+    /// # Safety
+    ///
+    /// The returned guard must be dropped (not leaked) before any frame opened
+    /// previously is closed. Use [`ocaml_frame!`](crate::ocaml_frame) or
+    /// [`LocalRoots::scope`] instead, which guarantee it.
+    #[doc(hidden)]
+    pub unsafe fn open<'r>(
+        &'r mut self,
+        _cr: &OCamlRuntime,
+    ) -> (LocalRootsFrame<'r>, [LocalRoot<'r>; N]) {
+        let block = self.block.get();
+        (*block).next = local_roots();
+        (*block).ntables = 1;
+        (*block).nitems = N as isize;
+        (*block).tables[0] = self.slots.as_ptr() as *mut RawOCaml;
+        set_local_roots(block);
+        let roots = std::array::from_fn(|i| LocalRoot {
+            slot: self.slots[i].get(),
+            _marker: PhantomData,
+        });
+        let frame = LocalRootsFrame {
+            block,
+            _marker: PhantomData,
+        };
+        (frame, roots)
+    }
+}
+
+impl<const N: usize> Default for LocalRoots<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guard for an open frame of [`LocalRoots`], unlinks the roots when dropped.
+#[doc(hidden)]
+pub struct LocalRootsFrame<'r> {
+    block: *mut CamlRootsBlock,
+    _marker: PhantomData<&'r mut ()>,
+}
+
+impl Drop for LocalRootsFrame<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            debug_assert!(
+                local_roots() == self.block,
+                "local root frames must be closed in the reverse order they were opened"
+            );
+            set_local_roots((*self.block).next);
+        }
+    }
+}
+
+/// A local root slot, obtained from [`LocalRoots::scope`] or [`ocaml_frame!`](crate::ocaml_frame).
+///
+/// Values rooted with [`LocalRoot::keep`] stay alive until the slot is reused
+/// or the frame is closed.
+pub struct LocalRoot<'r> {
+    slot: *mut RawOCaml,
+    _marker: PhantomData<&'r mut RawOCaml>,
+}
+
+impl<'r> LocalRoot<'r> {
+    /// Roots the OCaml value `val`, returning an [`OCamlRef`]`<T>`.
+    pub fn keep<'tmp, T>(&'tmp mut self, val: OCaml<T>) -> OCamlRef<'tmp, T> {
+        unsafe {
+            *self.slot = val.raw();
+            OCamlCell::create_ref(self.slot)
+        }
+    }
+}
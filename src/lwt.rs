@@ -0,0 +1,168 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+// Conversions between Lwt promises and Rust futures.
+//
+// Lwt is not thread-safe, so all the interaction with promises happens through two
+// helpers registered from the OCaml side: `ocaml_interop_lwt_on_resolve`, which attaches
+// a callback to a promise, and `ocaml_interop_lwt_wait`, which creates a pending promise
+// together with a resolver that can be called from any thread holding the domain lock
+// and defers the actual resolution to the Lwt main loop.
+
+use std::{
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use crate::{
+    boxroot::BoxRoot,
+    closure::OCamlClosure,
+    error::OCamlExceptionError,
+    global_root::SendableRoot,
+    mlvalues::{OCamlException, UNIT},
+    rust_closure::alloc_rust_closure1,
+    OCaml, OCamlRef, OCamlRuntime, ToOCaml,
+};
+
+const ON_RESOLVE_NAME: &str = "ocaml_interop_lwt_on_resolve";
+const WAIT_NAME: &str = "ocaml_interop_lwt_wait";
+
+/// `'a Lwt.t`
+pub struct LwtPromise<A> {
+    _marker: PhantomData<A>,
+}
+
+type LwtResult<T> = Result<SendableRoot<T>, OCamlExceptionError>;
+
+struct LwtState<T: 'static> {
+    result: Option<LwtResult<T>>,
+    waker: Option<Waker>,
+}
+
+/// Future that resolves when an Lwt promise is resolved, created with [`LwtFuture::new`].
+///
+/// Resolves to the value of the promise, or to the exception it was rejected with. The
+/// value is kept in a [`SendableRoot`] because the future is usually polled on a different
+/// thread than the one running the Lwt main loop.
+pub struct LwtFuture<T: 'static> {
+    state: Arc<Mutex<LwtState<T>>>,
+}
+
+impl<T: 'static> LwtFuture<T> {
+    /// Creates a future that resolves when `promise` does.
+    ///
+    /// Requires the `ocaml_interop_lwt_on_resolve` helper and the
+    /// `ocaml_interop_closure_call1` dispatcher to be registered from the OCaml side,
+    /// see [Part 6: Advanced Topics](crate::user_guides::part6_advanced_topics).
+    pub fn new(cr: &mut OCamlRuntime, promise: OCamlRef<LwtPromise<T>>) -> LwtFuture<T> {
+        let on_resolve = named_helper(ON_RESOLVE_NAME);
+        let state = Arc::new(Mutex::new(LwtState {
+            result: None,
+            waker: None,
+        }));
+        let callback_state = state.clone();
+        let callback = alloc_rust_closure1::<Result<T, OCamlException>, ()>(cr, move |_cr, raw| {
+            let result: OCaml<Result<T, OCamlException>> = OCaml {
+                _marker: PhantomData,
+                raw,
+            };
+            let result = match result.to_result() {
+                Ok(value) => Ok(SendableRoot::new(value)),
+                Err(exception) => Err(OCamlExceptionError::new(exception)),
+            };
+            let waker = {
+                let mut state = callback_state.lock().unwrap_or_else(|e| e.into_inner());
+                state.result = Some(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+            UNIT
+        });
+        let callback = BoxRoot::new(callback);
+        let _: OCaml<()> = on_resolve.call2(cr, promise, &callback);
+        LwtFuture { state }
+    }
+}
+
+impl<T: 'static> Future for LwtFuture<T> {
+    type Output = LwtResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<LwtResult<T>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Future returned by [`LwtPromise::from_future`] that drives a Rust future and resolves
+/// the corresponding Lwt promise with its result.
+pub struct LwtResolver {
+    future: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+impl Future for LwtResolver {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl<T: 'static> LwtPromise<T> {
+    /// Creates a pending Lwt promise that will be resolved with the result of `future`.
+    ///
+    /// Returns the promise and an [`LwtResolver`] future that must be spawned on the Rust
+    /// async runtime. When `future` completes, the resolver acquires the domain lock with
+    /// [`OCamlRuntime::with_domain_lock`] and schedules the resolution of the promise on
+    /// the Lwt main loop: `Ok` values are converted to OCaml, and `Err(message)` rejects
+    /// the promise with `Failure message`. The resolver must not be polled from a thread
+    /// that holds the domain lock.
+    ///
+    /// Requires the `ocaml_interop_lwt_wait` helper to be registered from the OCaml side,
+    /// see [Part 6: Advanced Topics](crate::user_guides::part6_advanced_topics).
+    pub fn from_future<'a, F, R>(
+        cr: &'a mut OCamlRuntime,
+        future: F,
+    ) -> (OCaml<'a, LwtPromise<T>>, LwtResolver)
+    where
+        F: Future<Output = Result<R, String>> + Send + 'static,
+        R: ToOCaml<T> + Send + 'static,
+    {
+        let wait = named_helper(WAIT_NAME);
+        let pending: BoxRoot<PendingPromise<T>> = BoxRoot::new(wait.call(cr, &OCaml::unit()));
+        let resolve = SendableRoot::new(pending.get(cr).snd());
+        let resolver = LwtResolver {
+            future: Box::pin(async move {
+                let result = future.await;
+                OCamlRuntime::with_domain_lock(move |cr| {
+                    let resolve = BoxRoot::new(resolve.get(cr));
+                    if let Err(exception) = resolve.try_call(cr, &result) {
+                        panic!(
+                            "OCaml exception while resolving Lwt promise, message: {:?}",
+                            exception.message()
+                        );
+                    }
+                })
+            }),
+        };
+        (pending.get(cr).fst(), resolver)
+    }
+}
+
+type PendingPromise<T> = (LwtPromise<T>, fn(Result<T, String>) -> ());
+
+fn named_helper(name: &str) -> OCamlClosure {
+    OCamlClosure::named(name).unwrap_or_else(|| {
+        panic!("OCaml closure with name '{name}' not registered, it is required for Lwt promises")
+    })
+}
@@ -133,6 +133,38 @@ macro_rules! ocaml {
     }
 }
 
+/// Opens a frame of local roots for the duration of a block.
+///
+/// `ocaml_frame!(cr, (root1, root2, ...), { ... })` declares the local root variables
+/// `root1`, `root2`, etc. (see [`LocalRoot`]), that are valid inside the block.
+/// Rooting a value with [`LocalRoot::keep`] is cheaper than allocating a [`BoxRoot`],
+/// which makes local roots a good fit for temporaries in hot paths.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use ocaml_interop::*;
+/// # ocaml! { fn make_tuple(fst: String, snd: OCamlInt) -> (String, OCamlInt); }
+/// fn build_tuple(cr: &mut OCamlRuntime, name: &str, count: i64) -> (String, i64) {
+///     ocaml_frame!(cr, (name_root, count_root), {
+///         let name = name_root.keep(name.to_ocaml(cr));
+///         let count = count_root.keep(count.to_ocaml(cr));
+///         make_tuple(cr, name, count).to_rust(cr)
+///     })
+/// }
+/// ```
+#[macro_export]
+macro_rules! ocaml_frame {
+    ($cr:ident, ($($root:ident),+ $(,)?), $body:block) => {{
+        let mut __ocaml_frame_storage =
+            $crate::LocalRoots::<{ $crate::count_fields!($($root)*) }>::new();
+        #[allow(unused_mut)]
+        let (__ocaml_frame_guard, [$(mut $root),+]) =
+            unsafe { __ocaml_frame_storage.open(&*$cr) };
+        $body
+    }};
+}
+
 // Internal utility macros
 
 #[doc(hidden)]
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 pub use ocaml_sys::{
-    Tag, CLOSURE, DOUBLE_ARRAY, NO_SCAN, STRING, TAG_CONS as CONS, TAG_SOME as SOME,
+    Tag, CLOSURE, DOUBLE_ARRAY, NO_SCAN, OBJECT, STRING, TAG_CONS as CONS, TAG_SOME as SOME,
 };
 
 pub const TAG_POLYMORPHIC_VARIANT: Tag = 0;
@@ -3,12 +3,17 @@
 
 use ocaml_boxroot_sys::boxroot_teardown;
 use std::{
-    cell::UnsafeCell,
+    cell::{Cell, UnsafeCell},
     marker::PhantomData,
     ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Condvar, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use crate::{memory::OCamlRef, value::OCaml};
+use crate::{error::OCamlDomainLockTimeout, memory::OCamlRef, value::OCaml};
 
 thread_local! {
   static TLS_RUNTIME: UnsafeCell<OCamlRuntime> = const { UnsafeCell::new({
@@ -56,35 +61,10 @@ impl OCamlRuntime {
     /// OCaml runtime shutdown and release resources once dropped.
     ///
     /// Returns `Err(String)` if called more than once.
+    ///
+    /// Use [`OCamlRuntimeBuilder`] to customize the program arguments and runtime parameters.
     pub fn init() -> Result<OCamlRuntimeStartupGuard, String> {
-        #[cfg(not(feature = "no-caml-startup"))]
-        {
-            use std::sync::atomic::{AtomicBool, Ordering};
-
-            static INIT_CALLED: AtomicBool = AtomicBool::new(false);
-
-            if INIT_CALLED
-                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-                .is_err()
-            {
-                return Err("OCaml runtime already initialized".to_string());
-            }
-            unsafe {
-                let arg0 = c"ocaml".as_ptr() as *const ocaml_sys::Char;
-                let args = [arg0, core::ptr::null()];
-                ocaml_sys::caml_startup(args.as_ptr());
-                ocaml_boxroot_sys::boxroot_setup();
-                ocaml_sys::caml_enter_blocking_section();
-            }
-
-            Ok(OCamlRuntimeStartupGuard {
-                _not_send_sync: PhantomData,
-            })
-        }
-        #[cfg(feature = "no-caml-startup")]
-        return Err(
-            "Rust code called from OCaml should not try to initialize the runtime".to_string(),
-        );
+        OCamlRuntimeBuilder::new().init()
     }
 
     /// Release the OCaml runtime lock, call `f`, and re-acquire the OCaml runtime lock.
@@ -110,9 +90,432 @@ impl OCamlRuntime {
     where
         F: FnOnce(&mut Self) -> T,
     {
+        let started = Instant::now();
+        let _user = RUST_USERS.register();
         let mut lock = OCamlDomainLock::new();
+        DOMAIN_LOCK_STATS.record_acquisition(started.elapsed());
         f(&mut lock)
     }
+
+    /// Like [`OCamlRuntime::with_domain_lock`], but returns `None` without calling `f`
+    /// if another Rust thread is currently using or waiting for the runtime.
+    ///
+    /// Only threads that entered or are waiting to enter the runtime through the
+    /// `with_domain_lock` family of functions, and are not inside
+    /// [`OCamlRuntime::releasing_runtime`], are taken into account. The OCaml C API can't
+    /// try to acquire the domain lock without blocking, so an OCaml thread running at the
+    /// moment can still delay the call until it yields the runtime.
+    pub fn try_with_domain_lock<F, T>(f: F) -> Option<T>
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        Self::with_domain_lock_timeout(Duration::ZERO, f).ok()
+    }
+
+    /// Like [`OCamlRuntime::with_domain_lock`], but gives up and returns
+    /// `Err(OCamlDomainLockTimeout)` without calling `f` if the runtime couldn't be
+    /// entered within `timeout`.
+    ///
+    /// See [`OCamlRuntime::try_with_domain_lock`] for the threads this function waits for.
+    pub fn with_domain_lock_timeout<F, T>(
+        timeout: Duration,
+        f: F,
+    ) -> Result<T, OCamlDomainLockTimeout>
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        let started = Instant::now();
+        let Some(_user) = RUST_USERS.reserve(started + timeout) else {
+            DOMAIN_LOCK_STATS.record_timeout(started.elapsed());
+            return Err(OCamlDomainLockTimeout);
+        };
+        let mut lock = OCamlDomainLock::new();
+        DOMAIN_LOCK_STATS.record_acquisition(started.elapsed());
+        Ok(f(&mut lock))
+    }
+
+    /// Returns statistics on how long threads waited to acquire the domain lock
+    /// through the `with_domain_lock` family of functions.
+    pub fn domain_lock_stats() -> OCamlDomainLockStats {
+        DOMAIN_LOCK_STATS.snapshot()
+    }
+}
+
+/// Statistics on the domain lock acquisitions, see [`OCamlRuntime::domain_lock_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OCamlDomainLockStats {
+    /// Number of times the domain lock was acquired, including the re-acquisitions
+    /// after [`OCamlRuntime::releasing_runtime`].
+    pub acquisitions: u64,
+    /// Number of times a thread gave up waiting for the domain lock.
+    pub timeouts: u64,
+    /// Total time spent waiting for the domain lock, including the waits that timed out.
+    pub total_wait: Duration,
+    /// Longest single wait for the domain lock.
+    pub max_wait: Duration,
+}
+
+struct DomainLockStatsCounters {
+    acquisitions: AtomicU64,
+    timeouts: AtomicU64,
+    total_wait_nanos: AtomicU64,
+    max_wait_nanos: AtomicU64,
+}
+
+static DOMAIN_LOCK_STATS: DomainLockStatsCounters = DomainLockStatsCounters {
+    acquisitions: AtomicU64::new(0),
+    timeouts: AtomicU64::new(0),
+    total_wait_nanos: AtomicU64::new(0),
+    max_wait_nanos: AtomicU64::new(0),
+};
+
+impl DomainLockStatsCounters {
+    fn record_acquisition(&self, wait: Duration) {
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        self.record_wait(wait);
+    }
+
+    fn record_timeout(&self, wait: Duration) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+        self.record_wait(wait);
+    }
+
+    fn record_wait(&self, wait: Duration) {
+        let nanos = u64::try_from(wait.as_nanos()).unwrap_or(u64::MAX);
+        self.total_wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_wait_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> OCamlDomainLockStats {
+        OCamlDomainLockStats {
+            acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(self.total_wait_nanos.load(Ordering::Relaxed)),
+            max_wait: Duration::from_nanos(self.max_wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+// Number of Rust threads currently using or waiting for the runtime through the
+// `with_domain_lock` family of functions, outside of `releasing_runtime`.
+//
+// The OCaml C API has no way to try to acquire the domain lock without blocking, so
+// `with_domain_lock_timeout` waits until no other Rust thread is known to be using the
+// runtime, and registers itself before letting another thread check, so that it is the
+// only one trying to acquire it. This doesn't restrict access to the runtime:
+// `with_domain_lock` registers itself without waiting, and OCaml code releasing the
+// runtime by itself doesn't update it.
+static RUST_USERS: RustUsers = RustUsers {
+    count: Mutex::new(0),
+    released: Condvar::new(),
+};
+
+thread_local! {
+    static USING_RUNTIME: Cell<bool> = const { Cell::new(false) };
+    static DOMAIN_LOCK_HELD: Cell<bool> = const { Cell::new(false) };
+}
+
+// Tracks whether the current thread is known to hold a domain lock, for operations that
+// can't be performed without it (like removing global roots). It is only a lower bound:
+// a thread running Rust code called directly from OCaml holds the lock without it being
+// recorded here.
+pub(crate) fn set_domain_lock_held(held: bool) -> bool {
+    DOMAIN_LOCK_HELD.with(|cell| cell.replace(held))
+}
+
+pub(crate) fn domain_lock_held() -> bool {
+    DOMAIN_LOCK_HELD.with(|cell| cell.get())
+}
+
+struct RustUsers {
+    count: Mutex<usize>,
+    released: Condvar,
+}
+
+impl RustUsers {
+    // Waits until no Rust thread uses the runtime, and registers the current thread while
+    // still holding the mutex. Returns `None` if `deadline` passed first.
+    fn reserve(&self, deadline: Instant) -> Option<RustUserGuard> {
+        let mut count = self.count.lock().unwrap_or_else(|e| e.into_inner());
+        while *count > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            count = self
+                .released
+                .wait_timeout(count, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        *count += 1;
+        USING_RUNTIME.with(|using| using.set(true));
+        Some(RustUserGuard {
+            _not_send_sync: PhantomData,
+        })
+    }
+
+    fn register(&self) -> RustUserGuard {
+        self.enter();
+        RustUserGuard {
+            _not_send_sync: PhantomData,
+        }
+    }
+
+    fn enter(&self) {
+        USING_RUNTIME.with(|using| using.set(true));
+        *self.count.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+    }
+
+    fn leave(&self) {
+        USING_RUNTIME.with(|using| using.set(false));
+        let mut count = self.count.lock().unwrap_or_else(|e| e.into_inner());
+        *count -= 1;
+        if *count == 0 {
+            self.released.notify_all();
+        }
+    }
+
+    fn is_used_by_current_thread(&self) -> bool {
+        USING_RUNTIME.with(|using| using.get())
+    }
+}
+
+struct RustUserGuard {
+    _not_send_sync: PhantomData<*const ()>,
+}
+
+impl Drop for RustUserGuard {
+    fn drop(&mut self) {
+        RUST_USERS.leave();
+    }
+}
+
+/// Builder for initializing the OCaml runtime with custom settings.
+///
+/// ```rust,no_run
+/// use ocaml_interop::OCamlRuntimeBuilder;
+///
+/// let _guard = OCamlRuntimeBuilder::new()
+///     .argv(std::env::args())
+///     .minor_heap_size(8 * 1024 * 1024)
+///     .catch_startup_exceptions(true)
+///     .init()
+///     .expect("Failed to initialize the OCaml runtime");
+/// ```
+#[derive(Debug, Clone)]
+pub struct OCamlRuntimeBuilder {
+    argv: Vec<String>,
+    minor_heap_size: Option<usize>,
+    stack_limit: Option<usize>,
+    verbose: Option<u32>,
+    pooled: bool,
+    catch_startup_exceptions: bool,
+}
+
+impl Default for OCamlRuntimeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OCamlRuntimeBuilder {
+    /// Creates a builder with the default settings, equivalent to [`OCamlRuntime::init`].
+    pub fn new() -> Self {
+        OCamlRuntimeBuilder {
+            argv: vec!["ocaml".to_string()],
+            minor_heap_size: None,
+            stack_limit: None,
+            verbose: None,
+            pooled: false,
+            catch_startup_exceptions: false,
+        }
+    }
+
+    /// Sets the program arguments, as seen by OCaml through `Sys.argv`.
+    ///
+    /// The first argument is the program name. Defaults to `["ocaml"]`.
+    pub fn argv<I, S>(mut self, argv: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.argv = argv.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the size of the minor heap, in words (`s` in `OCAMLRUNPARAM`).
+    pub fn minor_heap_size(mut self, words: usize) -> Self {
+        self.minor_heap_size = Some(words);
+        self
+    }
+
+    /// Sets the maximum size of the fiber stacks, in words (`l` in `OCAMLRUNPARAM`).
+    pub fn stack_limit(mut self, words: usize) -> Self {
+        self.stack_limit = Some(words);
+        self
+    }
+
+    /// Sets the bitmask controlling the GC messages printed on stderr (`v` in `OCAMLRUNPARAM`).
+    pub fn verbose(mut self, mask: u32) -> Self {
+        self.verbose = Some(mask);
+        self
+    }
+
+    /// Use `caml_startup_pooled`, so that all the memory allocated by the runtime is
+    /// released when it is shut down.
+    pub fn pooled(mut self, pooled: bool) -> Self {
+        self.pooled = pooled;
+        self
+    }
+
+    /// Use `caml_startup_exn` so that an exception raised while initializing the OCaml
+    /// modules results in an `Err` instead of aborting the program.
+    ///
+    /// The runtime can't be used after such a failure, and can't be initialized again.
+    pub fn catch_startup_exceptions(mut self, catch: bool) -> Self {
+        self.catch_startup_exceptions = catch;
+        self
+    }
+
+    /// Initialize the OCaml runtime exactly once with these settings.
+    ///
+    /// Parameters set in this builder are appended to the ones in `OCAMLRUNPARAM` (or
+    /// `CAMLRUNPARAM`) and take precedence over them. The variable is set for the duration
+    /// of the runtime startup and restored afterwards, so it should not be accessed by
+    /// other threads during this call.
+    ///
+    /// Returns `Err(String)` if the runtime was already initialized, if an argument
+    /// contains a NUL byte, or if the OCaml modules raised an exception during
+    /// initialization and [`OCamlRuntimeBuilder::catch_startup_exceptions`] is enabled.
+    /// In the latter case the runtime is left unusable.
+    pub fn init(self) -> Result<OCamlRuntimeStartupGuard, String> {
+        #[cfg(not(feature = "no-caml-startup"))]
+        {
+            use std::{
+                ffi::CString,
+                sync::atomic::{AtomicBool, Ordering},
+            };
+
+            static INIT_CALLED: AtomicBool = AtomicBool::new(false);
+
+            let argv = self
+                .argv
+                .iter()
+                .map(|arg| CString::new(arg.as_str()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| "OCaml runtime arguments cannot contain NUL bytes".to_string())?;
+            let mut argv_ptrs: Vec<*mut ocaml_sys::Char> = argv
+                .iter()
+                .map(|arg| arg.as_ptr() as *mut ocaml_sys::Char)
+                .collect();
+            argv_ptrs.push(core::ptr::null_mut());
+
+            if INIT_CALLED
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                return Err("OCaml runtime already initialized".to_string());
+            }
+            let runparam = self.runparam();
+            let previous_runparam = std::env::var_os(RUNPARAM_VAR);
+            if let Some(runparam) = &runparam {
+                std::env::set_var(RUNPARAM_VAR, runparam);
+            }
+            let startup_error = unsafe {
+                let argv = argv_ptrs.as_mut_ptr();
+                if self.catch_startup_exceptions {
+                    let result = if self.pooled {
+                        caml_startup_pooled_exn(argv)
+                    } else {
+                        caml_startup_exn(argv)
+                    };
+                    let cr = internal::recover_runtime_handle();
+                    OCaml::of_exception_result(cr, result).map(|exception| {
+                        match exception.message() {
+                            Some(message) => {
+                                format!("OCaml exception raised during startup: {message}")
+                            }
+                            None => "OCaml exception raised during startup".to_string(),
+                        }
+                    })
+                } else if self.pooled {
+                    caml_startup_pooled(argv);
+                    None
+                } else {
+                    ocaml_sys::caml_startup(argv as *const *const ocaml_sys::Char);
+                    None
+                }
+            };
+            if runparam.is_some() {
+                match previous_runparam {
+                    Some(previous) => std::env::set_var(RUNPARAM_VAR, previous),
+                    None => std::env::remove_var(RUNPARAM_VAR),
+                }
+            }
+            unsafe {
+                if startup_error.is_none() {
+                    ocaml_boxroot_sys::boxroot_setup();
+                }
+                // Release the domain lock even on failure, so that other threads don't
+                // block forever trying to acquire it.
+                ocaml_sys::caml_enter_blocking_section();
+            }
+            if let Some(error) = startup_error {
+                return Err(error);
+            }
+
+            Ok(OCamlRuntimeStartupGuard {
+                _not_send_sync: PhantomData,
+            })
+        }
+        #[cfg(feature = "no-caml-startup")]
+        return Err(
+            "Rust code called from OCaml should not try to initialize the runtime".to_string(),
+        );
+    }
+
+    // Builds the `OCAMLRUNPARAM` value parsed by the runtime at startup, or `None` if no
+    // parameter was set. Later settings override earlier ones, so the builder's are
+    // appended to the ones already in the environment.
+    #[cfg(not(feature = "no-caml-startup"))]
+    fn runparam(&self) -> Option<std::ffi::OsString> {
+        let mut params = Vec::new();
+        if let Some(words) = self.minor_heap_size {
+            params.push(format!("s={words}"));
+        }
+        if let Some(words) = self.stack_limit {
+            params.push(format!("l={words}"));
+        }
+        if let Some(mask) = self.verbose {
+            params.push(format!("v={mask}"));
+        }
+        if params.is_empty() {
+            return None;
+        }
+        // The runtime only reads `CAMLRUNPARAM` when `OCAMLRUNPARAM` is not set
+        let mut runparam = std::env::var_os(RUNPARAM_VAR)
+            .or_else(|| std::env::var_os("CAMLRUNPARAM"))
+            .unwrap_or_default();
+        for param in params {
+            if !runparam.is_empty() {
+                runparam.push(",");
+            }
+            runparam.push(param);
+        }
+        Some(runparam)
+    }
+}
+
+#[cfg(not(feature = "no-caml-startup"))]
+const RUNPARAM_VAR: &str = "OCAMLRUNPARAM";
+
+// Startup variants not exposed by `ocaml-sys`
+#[cfg(not(feature = "no-caml-startup"))]
+extern "C" {
+    fn caml_startup_exn(argv: *mut *mut ocaml_sys::Char) -> crate::RawOCaml;
+    fn caml_startup_pooled(argv: *mut *mut ocaml_sys::Char);
+    fn caml_startup_pooled_exn(argv: *mut *mut ocaml_sys::Char) -> crate::RawOCaml;
 }
 
 impl Drop for OCamlRuntimeStartupGuard {
@@ -125,17 +528,28 @@ impl Drop for OCamlRuntimeStartupGuard {
     }
 }
 
-struct OCamlBlockingSection;
+struct OCamlBlockingSection {
+    user_left: bool,
+    lock_was_held: bool,
+}
 
 impl OCamlBlockingSection {
     fn new() -> Self {
-        Self
+        Self {
+            user_left: false,
+            lock_was_held: false,
+        }
     }
 
-    fn perform<T, F>(self, f: F) -> T
+    fn perform<T, F>(mut self, f: F) -> T
     where
         F: FnOnce() -> T,
     {
+        self.lock_was_held = set_domain_lock_held(false);
+        if RUST_USERS.is_used_by_current_thread() {
+            RUST_USERS.leave();
+            self.user_left = true;
+        }
         unsafe { ocaml_sys::caml_enter_blocking_section() };
         f()
     }
@@ -143,7 +557,13 @@ impl OCamlBlockingSection {
 
 impl Drop for OCamlBlockingSection {
     fn drop(&mut self) {
+        if self.user_left {
+            RUST_USERS.enter();
+        }
+        let started = Instant::now();
         unsafe { ocaml_sys::caml_leave_blocking_section() };
+        DOMAIN_LOCK_STATS.record_acquisition(started.elapsed());
+        set_domain_lock_held(self.lock_was_held);
     }
 }
 
@@ -181,6 +601,9 @@ impl OCamlDomainLock {
         unsafe {
             ocaml_sys::caml_leave_blocking_section();
         };
+        set_domain_lock_held(true);
+        crate::global_root::remove_pending_roots();
+        crate::gc::release_pending_profiles();
         Self {
             _not_send_sync: PhantomData,
         }
@@ -189,6 +612,7 @@ impl OCamlDomainLock {
 
 impl Drop for OCamlDomainLock {
     fn drop(&mut self) {
+        set_domain_lock_held(false);
         unsafe {
             ocaml_sys::caml_enter_blocking_section();
         };
@@ -0,0 +1,217 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+// OCaml closures backed by Rust closures.
+//
+// OCaml closures can't be allocated from Rust directly, so instead the OCaml side
+// registers an `external` dispatcher for each arity (`ocaml_interop_closure_callN`) and
+// the Rust closure is stored in a `DynBox` that gets partially applied to it. The result
+// is a regular OCaml closure that calls back into `dispatch` when applied.
+//
+// OCaml code can pass the closure to another domain and apply it there, so the Rust
+// closure has to be `Send`, and is kept behind a `Mutex` so that it is never called from
+// two domains at once.
+
+use std::{
+    borrow::Borrow,
+    marker::PhantomData,
+    sync::{Mutex, TryLockError},
+};
+
+use crate::{
+    boxroot::BoxRoot,
+    closure::OCamlClosure,
+    memory::alloc_box,
+    mlvalues::{DynBox, RawOCaml},
+    runtime::{internal::recover_runtime_handle_mut, set_domain_lock_held},
+    OCaml, OCamlRef, OCamlRuntime,
+};
+
+type RustClosureFn = Box<dyn FnMut(&mut OCamlRuntime, &[RawOCaml]) -> RawOCaml + Send>;
+type RustClosureBody = Mutex<RustClosureFn>;
+
+const DISPATCHER_NAMES: [&str; 5] = [
+    "ocaml_interop_closure_call1",
+    "ocaml_interop_closure_call2",
+    "ocaml_interop_closure_call3",
+    "ocaml_interop_closure_call4",
+    "ocaml_interop_closure_call5",
+];
+
+/// Allocates an OCaml closure of one argument that calls `f` when applied.
+///
+/// Panics if the dispatcher hasn't been registered from the OCaml side with:
+///
+/// ```ocaml
+/// external ocaml_interop_closure_call1 : 'f -> 'a -> 'r = "ocaml_interop_closure_call1"
+/// let () = Callback.register "ocaml_interop_closure_call1" ocaml_interop_closure_call1
+/// ```
+pub(crate) fn alloc_rust_closure1<'a, A, R>(
+    cr: &'a mut OCamlRuntime,
+    f: impl FnMut(&mut OCamlRuntime, RawOCaml) -> RawOCaml + Send + 'static,
+) -> OCaml<'a, fn(A) -> R> {
+    let mut f = f;
+    alloc_rust_closure(cr, 1, Box::new(move |cr, args| f(cr, args[0])))
+}
+
+// Allocates an OCaml closure of `arity` arguments that calls `body` with them.
+fn alloc_rust_closure<'a, T>(
+    cr: &'a mut OCamlRuntime,
+    arity: usize,
+    body: RustClosureFn,
+) -> OCaml<'a, T> {
+    let name = DISPATCHER_NAMES[arity - 1];
+    let dispatcher = OCamlClosure::named(name).unwrap_or_else(|| {
+        panic!("OCaml closure with name '{name}' not registered, it is required for Rust closures")
+    });
+    let token = BoxRoot::new(alloc_box(cr, Mutex::new(body)));
+    // The dispatcher is an `external` taking the token as an extra first argument,
+    // applying it to the token alone produces a closure.
+    dispatcher.call(cr, &token)
+}
+
+macro_rules! from_rust_closure_impl {
+    ($( { $arity:literal, ($($argname:ident: $ot:ident),+) } ),+ $(,)?) => {
+        $(
+            impl<'a, $($ot: 'static,)+ R: 'static> OCaml<'a, fn($($ot,)+) -> R> {
+                /// Allocates an OCaml closure that calls the Rust closure `f` when applied.
+                ///
+                /// The arguments are rooted before calling `f`, so they remain valid after
+                /// allocating through the runtime handle.
+                ///
+                /// There is one implementation for each arity, so the closure type has to be
+                /// given explicitly, for example
+                /// `OCaml::<fn(OCamlInt, OCamlInt) -> OCamlInt>::from_rust_closure(cr, f)`.
+                ///
+                /// Panics in `f` are raised as OCaml exceptions, like in exported functions.
+                /// Calls to `f` are not re-entrant: applying the closure again from inside `f`,
+                /// or from another domain while `f` is running, raises an exception.
+                ///
+                /// `f` has to be `Send` because OCaml code can pass the closure to another
+                /// domain and apply it there.
+                ///
+                #[doc = concat!(
+                    "Requires the `ocaml_interop_closure_call", $arity, "` dispatcher to be ",
+                    "registered from the OCaml side, see [Part 6: Advanced Topics]",
+                    "(crate::user_guides::part6_advanced_topics)."
+                )]
+                pub fn from_rust_closure<F>(cr: &'a mut OCamlRuntime, f: F) -> Self
+                where
+                    F: for<'c, 'r> FnMut(&'c mut OCamlRuntime, $(OCamlRef<'r, $ot>),+) -> OCaml<'c, R>
+                        + Send
+                        + 'static,
+                {
+                    let mut f = f;
+                    let body: RustClosureFn = Box::new(move |cr, args| match *args {
+                        [$($argname),+] => {
+                            $(let $argname: BoxRoot<$ot> =
+                                BoxRoot::new(OCaml { _marker: PhantomData, raw: $argname });)+
+                            let result = f(cr, $(&$argname),+);
+                            unsafe { result.raw() }
+                        }
+                        _ => unreachable!("Rust closure called with the wrong number of arguments"),
+                    });
+                    alloc_rust_closure(cr, $arity, body)
+                }
+            }
+        )+
+    };
+}
+
+from_rust_closure_impl! {
+    { 1, (arg1: A1) },
+    { 2, (arg1: A1, arg2: A2) },
+    { 3, (arg1: A1, arg2: A2, arg3: A3) },
+    { 4, (arg1: A1, arg2: A2, arg3: A3, arg4: A4) },
+    { 5, (arg1: A1, arg2: A2, arg3: A3, arg4: A4, arg5: A5) },
+}
+
+unsafe fn dispatch(token: RawOCaml, args: &[RawOCaml]) -> RawOCaml {
+    let cr = recover_runtime_handle_mut();
+    let lock_was_held = set_domain_lock_held(true);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let token: OCaml<DynBox<RustClosureBody>> = OCaml {
+            _marker: PhantomData,
+            raw: token,
+        };
+        // The token is rooted for the duration of the call, otherwise nothing would
+        // keep it alive if the closure itself becomes unreachable while running.
+        let _token_root = BoxRoot::new(token);
+        let body: *const RustClosureBody = token.borrow();
+        // A panic in `f` poisons the mutex, but leaves the closure usable.
+        let mut f = match (*body).try_lock() {
+            Ok(f) => f,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                panic!("Rust closure called re-entrantly or from several domains at once")
+            }
+        };
+        f(cr, args)
+    }));
+    set_domain_lock_held(lock_was_held);
+    match result {
+        Ok(value) => value,
+        Err(panic_payload) => {
+            crate::internal::process_panic_payload_and_raise_ocaml_exception(panic_payload);
+            unreachable!("process_panic_payload_and_raise_ocaml_exception should not return");
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn ocaml_interop_closure_call1(token: RawOCaml, arg: RawOCaml) -> RawOCaml {
+    unsafe { dispatch(token, &[arg]) }
+}
+
+#[no_mangle]
+extern "C" fn ocaml_interop_closure_call2(
+    token: RawOCaml,
+    arg1: RawOCaml,
+    arg2: RawOCaml,
+) -> RawOCaml {
+    unsafe { dispatch(token, &[arg1, arg2]) }
+}
+
+#[no_mangle]
+extern "C" fn ocaml_interop_closure_call3(
+    token: RawOCaml,
+    arg1: RawOCaml,
+    arg2: RawOCaml,
+    arg3: RawOCaml,
+) -> RawOCaml {
+    unsafe { dispatch(token, &[arg1, arg2, arg3]) }
+}
+
+#[no_mangle]
+extern "C" fn ocaml_interop_closure_call4(
+    token: RawOCaml,
+    arg1: RawOCaml,
+    arg2: RawOCaml,
+    arg3: RawOCaml,
+    arg4: RawOCaml,
+) -> RawOCaml {
+    unsafe { dispatch(token, &[arg1, arg2, arg3, arg4]) }
+}
+
+#[no_mangle]
+extern "C" fn ocaml_interop_closure_call5(
+    token: RawOCaml,
+    arg1: RawOCaml,
+    arg2: RawOCaml,
+    arg3: RawOCaml,
+    arg4: RawOCaml,
+    arg5: RawOCaml,
+) -> RawOCaml {
+    unsafe { dispatch(token, &[arg1, arg2, arg3, arg4, arg5]) }
+}
+
+// Externals with more than 5 arguments need a separate implementation for bytecode.
+#[no_mangle]
+unsafe extern "C" fn ocaml_interop_closure_call5_byte(
+    argv: *const RawOCaml,
+    argn: std::os::raw::c_int,
+) -> RawOCaml {
+    debug_assert_eq!(argn, 6);
+    let args = std::slice::from_raw_parts(argv, 6);
+    dispatch(args[0], &args[1..])
+}
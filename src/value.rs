@@ -3,7 +3,7 @@
 
 use crate::{
     boxroot::BoxRoot,
-    error::OCamlFixnumConversionError,
+    error::{OCamlExceptionError, OCamlFixnumConversionError},
     memory::{alloc_box, OCamlCell},
     mlvalues::*,
     FromOCaml, OCamlRef, OCamlRuntime,
@@ -69,6 +69,16 @@ impl<'a, T> OCaml<'a, T> {
         }
     }
 
+    /// Reinterprets this value as an `OCaml<U>`, for types that share the same
+    /// representation, like `[@@unboxed]` types and their only field.
+    #[doc(hidden)]
+    pub unsafe fn cast<U>(&self) -> OCaml<'a, U> {
+        OCaml {
+            _marker: PhantomData,
+            raw: self.raw,
+        }
+    }
+
     #[doc(hidden)]
     pub fn is_block(&self) -> bool {
         is_block(self.raw)
@@ -546,7 +556,7 @@ pub enum RefOrRooted<'a, 'b, T: 'static> {
 }
 
 impl<T: 'static> RefOrRooted<'_, '_, T> {
-    unsafe fn get_raw(&self) -> RawOCaml {
+    pub(crate) unsafe fn get_raw(&self) -> RawOCaml {
         match self {
             RefOrRooted::Ref(a) => a.get_raw(),
             RefOrRooted::Root(a) => a.get_raw(),
@@ -584,6 +594,45 @@ where
     }
 }
 
+macro_rules! into_rust_fn_impl {
+    ($( { $(($argname:ident: $ot:ident $rt:ident)),+ } ),* $(,)?) => {
+        $(
+            impl<$($ot: 'static,)+ RetT: 'static> BoxRoot<fn($($ot,)+) -> RetT> {
+                /// Converts this OCaml closure into a Rust function that converts its
+                /// arguments to OCaml with [`ToOCaml`](crate::ToOCaml) and its result back to
+                /// Rust with [`FromOCaml`].
+                ///
+                /// Exceptions raised by the closure are returned as [`OCamlExceptionError`].
+                pub fn into_rust_fn<$($rt,)+ RustRetT>(
+                    self,
+                ) -> impl Fn(&mut OCamlRuntime, $($rt),+) -> Result<RustRetT, OCamlExceptionError>
+                where
+                    $($rt: crate::ToOCaml<$ot>,)+
+                    RustRetT: FromOCaml<RetT>,
+                {
+                    move |cr, $($argname),+| match self.try_call(cr, $(&$argname),+) {
+                        Ok(result) => Ok(result.to_rust()),
+                        Err(exception) => Err(OCamlExceptionError::new(exception)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+into_rust_fn_impl! {
+    { (arg1: OCaml1 Rust1) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3), (arg4: OCaml4 Rust4) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3), (arg4: OCaml4 Rust4),
+       (arg5: OCaml5 Rust5) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3), (arg4: OCaml4 Rust4),
+       (arg5: OCaml5 Rust5), (arg6: OCaml6 Rust6) },
+    { (arg1: OCaml1 Rust1), (arg2: OCaml2 Rust2), (arg3: OCaml3 Rust3), (arg4: OCaml4 Rust4),
+       (arg5: OCaml5 Rust5), (arg6: OCaml6 Rust6), (arg7: OCaml7 Rust7) },
+}
+
 macro_rules! try_call_impl {
     (
         $( { $method:ident, ($( ($argname:ident: $ot:ident $rt:ident) ),*) } ),*,
@@ -0,0 +1,215 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use std::{collections::HashMap, marker::PhantomData};
+
+use ocaml_sys::{int_val, val_int, Size};
+
+use crate::{boxroot::BoxRoot, mlvalues::RawOCaml, OCaml, OCamlRef, OCamlRuntime};
+
+// Ephemeron and weak array primitives, not exposed by `ocaml-sys`.
+// `Weak` arrays are ephemerons without data in OCaml 5.
+extern "C" {
+    fn caml_ephemeron_create(len: Size) -> RawOCaml;
+    fn caml_ephemeron_set_key(eph: RawOCaml, offset: Size, key: RawOCaml);
+    fn caml_ephemeron_unset_key(eph: RawOCaml, offset: Size);
+    fn caml_ephemeron_get_key(eph: RawOCaml, offset: Size, key: *mut RawOCaml) -> i32;
+    fn caml_ephemeron_key_is_set(eph: RawOCaml, offset: Size) -> i32;
+    fn caml_hash(count: RawOCaml, limit: RawOCaml, seed: RawOCaml, obj: RawOCaml) -> RawOCaml;
+}
+
+/// A weak reference to an OCaml value.
+///
+/// Unlike [`BoxRoot`]`<T>`, an `OCamlWeak<T>` does not keep the referenced value alive:
+/// once the value is no longer reachable from anywhere else the OCaml GC is free to
+/// collect it, after which [`OCamlWeak::get`] returns `None`.
+///
+/// Internally it is a rooted one-slot OCaml weak array (an ephemeron without data).
+pub struct OCamlWeak<T: 'static> {
+    ephemeron: BoxRoot<()>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> OCamlWeak<T> {
+    /// Creates a new weak reference to the value in `value`.
+    pub fn new(cr: &mut OCamlRuntime, value: OCamlRef<T>) -> OCamlWeak<T> {
+        let weak = Self::empty(cr);
+        weak.set(cr, value);
+        weak
+    }
+
+    /// Creates a new weak reference that doesn't point to any value.
+    pub fn empty(cr: &mut OCamlRuntime) -> OCamlWeak<T> {
+        let ephemeron = unsafe { OCaml::new(cr, caml_ephemeron_create(1)) };
+        OCamlWeak {
+            ephemeron: BoxRoot::new(ephemeron),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the referenced value, or `None` if it has been collected by the GC
+    /// (or was never set).
+    pub fn get<'a>(&self, cr: &'a OCamlRuntime) -> Option<OCaml<'a, T>> {
+        let mut raw = ocaml_sys::UNIT;
+        let found = unsafe { caml_ephemeron_get_key(self.raw_ephemeron(cr), 0, &mut raw) };
+        if found != 0 {
+            Some(unsafe { OCaml::new(cr, raw) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the referenced value is still alive.
+    pub fn is_alive(&self, cr: &OCamlRuntime) -> bool {
+        unsafe { caml_ephemeron_key_is_set(self.raw_ephemeron(cr), 0) != 0 }
+    }
+
+    /// Makes this weak reference point to the value in `value`.
+    pub fn set(&self, cr: &OCamlRuntime, value: OCamlRef<T>) {
+        unsafe { caml_ephemeron_set_key(self.raw_ephemeron(cr), 0, value.get_raw()) }
+    }
+
+    /// Clears this weak reference, so that [`OCamlWeak::get`] returns `None`.
+    pub fn clear(&self, cr: &OCamlRuntime) {
+        unsafe { caml_ephemeron_unset_key(self.raw_ephemeron(cr), 0) }
+    }
+
+    unsafe fn raw_ephemeron(&self, cr: &OCamlRuntime) -> RawOCaml {
+        self.ephemeron.get(cr).raw()
+    }
+}
+
+/// A Rust-side map keyed by OCaml values that doesn't keep its keys alive.
+///
+/// Keys are held through [`OCamlWeak`] references, so associating a Rust value with an
+/// OCaml value doesn't prevent the GC from collecting it. Entries whose key has been
+/// collected are dropped by [`OCamlWeakCache::purge`], and lazily when inserting.
+///
+/// Keys are compared by physical identity and bucketed by their structural hash
+/// (the same one computed by `Hashtbl.hash`), so keys must not be mutated in a way
+/// that changes their hash while they are in the cache.
+pub struct OCamlWeakCache<K: 'static, V> {
+    buckets: HashMap<isize, Vec<(OCamlWeak<K>, V)>>,
+    len: usize,
+}
+
+impl<K, V> OCamlWeakCache<K, V> {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        OCamlWeakCache {
+            buckets: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Number of entries in the cache.
+    ///
+    /// This count includes entries whose key has been collected but that haven't
+    /// been purged yet.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the value associated to `key`, if any.
+    pub fn get(&self, cr: &OCamlRuntime, key: OCamlRef<K>) -> Option<&V> {
+        let bucket = self.buckets.get(&hash_key(key))?;
+        let index = position_in_bucket(cr, bucket, key)?;
+        Some(&bucket[index].1)
+    }
+
+    /// Gets a mutable reference to the value associated to `key`, if any.
+    pub fn get_mut(&mut self, cr: &OCamlRuntime, key: OCamlRef<K>) -> Option<&mut V> {
+        let bucket = self.buckets.get_mut(&hash_key(key))?;
+        let index = position_in_bucket(cr, bucket, key)?;
+        Some(&mut bucket[index].1)
+    }
+
+    /// Returns `true` if there is a value associated to `key`.
+    pub fn contains_key(&self, cr: &OCamlRuntime, key: OCamlRef<K>) -> bool {
+        self.get(cr, key).is_some()
+    }
+
+    /// Associates `value` to `key`, returning the previous value if there was one.
+    pub fn insert(&mut self, cr: &mut OCamlRuntime, key: OCamlRef<K>, value: V) -> Option<V> {
+        let hash = hash_key(key);
+        if let Some(bucket) = self.buckets.get_mut(&hash) {
+            let before = bucket.len();
+            bucket.retain(|(weak, _)| weak.is_alive(cr));
+            self.len -= before - bucket.len();
+            if let Some(index) = position_in_bucket(cr, bucket, key) {
+                return Some(std::mem::replace(&mut bucket[index].1, value));
+            }
+        }
+        let weak = OCamlWeak::new(cr, key);
+        self.buckets.entry(hash).or_default().push((weak, value));
+        self.len += 1;
+        None
+    }
+
+    /// Removes the entry for `key`, returning its value if there was one.
+    pub fn remove(&mut self, cr: &OCamlRuntime, key: OCamlRef<K>) -> Option<V> {
+        let hash = hash_key(key);
+        let bucket = self.buckets.get_mut(&hash)?;
+        let index = position_in_bucket(cr, bucket, key)?;
+        let (_, value) = bucket.swap_remove(index);
+        if bucket.is_empty() {
+            self.buckets.remove(&hash);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Drops all entries whose key has been collected by the GC.
+    ///
+    /// Returns the amount of entries that were removed.
+    pub fn purge(&mut self, cr: &OCamlRuntime) -> usize {
+        let before = self.len;
+        self.buckets.retain(|_, bucket| {
+            bucket.retain(|(weak, _)| weak.is_alive(cr));
+            !bucket.is_empty()
+        });
+        self.len = self.buckets.values().map(Vec::len).sum();
+        before - self.len
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.len = 0;
+    }
+}
+
+impl<K, V> Default for OCamlWeakCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hash_key<K>(key: OCamlRef<K>) -> isize {
+    // Same parameters as `Hashtbl.hash`
+    unsafe {
+        int_val(caml_hash(
+            val_int(10),
+            val_int(100),
+            val_int(0),
+            key.get_raw(),
+        ))
+    }
+}
+
+fn position_in_bucket<K, V>(
+    cr: &OCamlRuntime,
+    bucket: &[(OCamlWeak<K>, V)],
+    key: OCamlRef<K>,
+) -> Option<usize> {
+    let raw_key = unsafe { key.get_raw() };
+    bucket.iter().position(|(weak, _)| {
+        weak.get(cr)
+            .is_some_and(|live_key| unsafe { live_key.raw() } == raw_key)
+    })
+}
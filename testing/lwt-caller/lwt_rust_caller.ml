@@ -0,0 +1,91 @@
+(* Copyright (c) Viable Systems and TezEdge Contributors
+   SPDX-License-Identifier: MIT *)
+
+exception RustPanic of string
+
+(* Helpers used by ocaml-interop to interact with Lwt promises *)
+
+external ocaml_interop_closure_call1 : 'f -> 'a -> 'r
+  = "ocaml_interop_closure_call1"
+
+let lwt_on_resolve p k =
+  Lwt.on_any p (fun v -> k (Ok v)) (fun e -> k (Error e))
+
+let lwt_wait () =
+  let p, r = Lwt.wait () in
+  let result = ref None in
+  let notification =
+    Lwt_unix.make_notification ~once:true (fun () ->
+        match !result with
+        | Some (Ok v) -> Lwt.wakeup_later r v
+        | Some (Error msg) -> Lwt.wakeup_later_exn r (Failure msg)
+        | None -> ())
+  in
+  let resolve res =
+    result := Some res;
+    Lwt_unix.send_notification notification
+  in
+  (p, resolve)
+
+let () =
+  Callback.register_exception "rust_panic_exn" (RustPanic "");
+  Callback.register "ocaml_interop_closure_call1" ocaml_interop_closure_call1;
+  Callback.register "ocaml_interop_lwt_on_resolve" lwt_on_resolve;
+  Callback.register "ocaml_interop_lwt_wait" lwt_wait
+
+module Rust = struct
+  external tests_teardown : unit -> unit = "ocaml_interop_teardown"
+  external lwt_double : int Lwt.t -> int Lwt.t = "rust_lwt_double"
+  external lwt_fail : string -> string Lwt.t = "rust_lwt_fail"
+  external lwt_greet : string -> string Lwt.t = "rust_lwt_greet"
+end
+
+let result_of_promise p =
+  Lwt_main.run
+    (Lwt.catch
+       (fun () -> Lwt.map (fun v -> Ok v) p)
+       (function Failure msg -> Lwt.return (Error msg) | e -> raise e))
+
+let test_greet () =
+  Alcotest.(check (result string string))
+    "Rust future resolves the promise" (Ok "Hello, Lwt!")
+    (result_of_promise (Rust.lwt_greet "Lwt"))
+
+let test_fail () =
+  Alcotest.(check (result string string))
+    "Rust future rejects the promise" (Error "failed in Rust")
+    (result_of_promise (Rust.lwt_fail "failed in Rust"))
+
+let test_double_pending () =
+  let p, r = Lwt.wait () in
+  let doubled = Rust.lwt_double p in
+  Lwt.wakeup_later r 21;
+  Alcotest.(check (result int string))
+    "Rust awaits a pending promise" (Ok 42)
+    (result_of_promise doubled)
+
+let test_double_resolved () =
+  Alcotest.(check (result int string))
+    "Rust awaits a resolved promise" (Ok 84)
+    (result_of_promise (Rust.lwt_double (Lwt.return 42)))
+
+let test_double_rejected () =
+  Alcotest.(check (result int string))
+    "Rust awaits a rejected promise"
+    (Error "OCaml exception raised: rejected")
+    (result_of_promise (Rust.lwt_double (Lwt.fail (Failure "rejected"))))
+
+let () =
+  let open Alcotest in
+  run "Lwt promises"
+    [
+      ( "LwtPromise",
+        [
+          test_case "Rust.lwt_greet" `Quick test_greet;
+          test_case "Rust.lwt_fail" `Quick test_fail;
+          test_case "Rust.lwt_double pending" `Quick test_double_pending;
+          test_case "Rust.lwt_double resolved" `Quick test_double_resolved;
+          test_case "Rust.lwt_double rejected" `Quick test_double_rejected;
+        ] );
+    ];
+  Rust.tests_teardown ()
@@ -0,0 +1,72 @@
+// Copyright (c) Viable Systems and TezEdge Contributors
+// SPDX-License-Identifier: MIT
+
+use ocaml_interop::{BoxRoot, LwtFuture, LwtPromise, LwtResolver, OCaml, OCamlInt, OCamlRuntime};
+use std::{
+    future::Future,
+    sync::Arc,
+    task::{Context, Poll, Wake},
+    thread,
+};
+
+// Minimal executor, each resolver is driven to completion on its own thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(ThreadWaker(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(value) => return value,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn spawn(resolver: LwtResolver) {
+    thread::spawn(move || block_on(resolver));
+}
+
+#[ocaml_interop::export]
+pub fn rust_lwt_double(
+    cr: &mut OCamlRuntime,
+    promise: BoxRoot<LwtPromise<OCamlInt>>,
+) -> OCaml<LwtPromise<OCamlInt>> {
+    let value = LwtFuture::new(cr, &promise);
+    let (doubled, resolver) = LwtPromise::from_future(cr, async move {
+        match value.await {
+            Ok(root) => {
+                let n: i64 = OCamlRuntime::with_domain_lock(|cr| root.get(cr).to_rust());
+                Ok(n * 2)
+            }
+            Err(exception) => Err(exception.to_string()),
+        }
+    });
+    spawn(resolver);
+    doubled
+}
+
+#[ocaml_interop::export]
+pub fn rust_lwt_fail(cr: &mut OCamlRuntime, message: OCaml<String>) -> OCaml<LwtPromise<String>> {
+    let message: String = message.to_rust();
+    let (promise, resolver) =
+        LwtPromise::from_future(cr, async move { Err::<String, String>(message) });
+    spawn(resolver);
+    promise
+}
+
+#[ocaml_interop::export]
+pub fn rust_lwt_greet(cr: &mut OCamlRuntime, name: OCaml<String>) -> OCaml<LwtPromise<String>> {
+    let name: String = name.to_rust();
+    let (promise, resolver) =
+        LwtPromise::from_future(cr, async move { Ok(format!("Hello, {name}!")) });
+    spawn(resolver);
+    promise
+}
@@ -1,6 +1,44 @@
 (* Copyright (c) Viable Systems and TezEdge Contributors
    SPDX-License-Identifier: MIT *)
 
+type point = { x : float; y : float }
+
+type user_id = UserId of int [@@unboxed]
+
+type reading = { sensor : string; celsius : float }
+
+type counter = {
+  label : string;
+  mutable count : int;
+  mutable last_update : string;
+}
+
+type velocity = { mutable dx : float; mutable dy : float }
+
+type tree = Leaf | Node of tree * int * tree
+
+type chain = { value : int; next : chain option }
+
+type rose = { label : int; children : rose list }
+
+type read_only
+
+type read_write
+
+type 'cap handle = { id : int }
+
+type plugin_event = ..
+
+type plugin_event += Started of string | Progress of int * int | Stopped
+
+exception Plugin_failure of string
+
+type circle = { radius : float }
+
+type label = { text : string; size : int }
+
+type shape = Circle of circle | Label of label | Square of { side : float }
+
 type movement =
   | Step of int
   | Expand of {
@@ -20,6 +58,12 @@ exception RustPanic of string
 
 let () = Callback.register_exception "rust_panic_exn" (RustPanic "")
 
+let () =
+  Callback.register "plugin_event.Started" [%extension_constructor Started];
+  Callback.register "plugin_event.Progress" [%extension_constructor Progress];
+  Callback.register "plugin_event.Stopped" [%extension_constructor Stopped];
+  Callback.register_exception "plugin_failure.Plugin_failure" (Plugin_failure "")
+
 module Rust = struct
   external tests_teardown : unit -> unit = "ocaml_interop_teardown"
   external twice : int -> int = "rust_twice"
@@ -67,6 +111,41 @@ module Rust = struct
   external call_ocaml_closure_and_return_exn : (int -> int) -> (int, exn) result
     = "rust_call_ocaml_closure_and_return_exn"
 
+  external mapi_with_callback :
+    (int -> string -> string) -> string list -> (string list, string) result
+    = "rust_mapi_with_callback"
+
+  external sort_with_comparator :
+    (string -> string -> int) -> string list -> (string list, string) result
+    = "rust_sort_with_comparator"
+
+  external join_with_callback5 :
+    (string -> string -> string -> string -> string -> string) ->
+    (string, string) result = "rust_join_with_callback5"
+
+  external scale_point : point -> point = "rust_scale_point"
+  external next_user_id : user_id -> user_id = "rust_next_user_id"
+  external warm_reading : reading -> reading = "rust_warm_reading"
+  external scale_shape : shape -> shape = "rust_scale_shape"
+  external bump_counter : counter -> unit = "rust_bump_counter"
+  external describe_counter : counter -> string = "rust_describe_counter"
+  external reverse_velocity : velocity -> unit = "rust_reverse_velocity"
+  external tree_roundtrip : tree -> tree = "rust_tree_roundtrip"
+  external increment_chain : chain -> chain = "rust_increment_chain"
+  external rose_roundtrip : rose -> rose = "rust_rose_roundtrip"
+
+  external make_writable : read_only handle -> read_write handle
+    = "rust_make_writable"
+
+  external bump_entry : int * string -> int * string = "rust_bump_entry"
+
+  external next_plugin_event : plugin_event -> plugin_event
+    = "rust_next_plugin_event"
+
+  external plugin_failure_message : exn -> string = "rust_plugin_failure_message"
+  external point_sum : point -> (float[@unboxed])
+    = "" "rust_point_sum"
+
   external rust_rust_add_7ints :
     int -> int -> int -> int -> int -> int -> int -> int
     = "rust_rust_add_7ints_byte" "rust_rust_add_7ints"
@@ -212,6 +291,163 @@ let test_call_ocaml_closure_and_return_exn () =
   Alcotest.(check (list (result int exn)))
     "Call a closure and return exn" expected result
 
+let test_mapi_with_callback () =
+  let expected =
+    [ Ok [ "0:a"; "2:b"; "4:c" ]; Error "callback failed at 1" ]
+  in
+  let result =
+    [
+      (* The callback calls back into Rust while Rust is iterating *)
+      Rust.mapi_with_callback
+        (fun i s -> string_of_int (Rust.twice i) ^ ":" ^ s)
+        [ "a"; "b"; "c" ];
+      Rust.mapi_with_callback
+        (fun i s ->
+          if i = 1 then failwith ("callback failed at " ^ string_of_int i)
+          else s)
+        [ "a"; "b"; "c" ];
+    ]
+  in
+  Alcotest.(check (list (result (list string) string)))
+    "Map with a callback" expected result
+
+let test_sort_with_comparator () =
+  let expected =
+    [
+      Ok [ "a"; "bb"; "ccc" ];
+      Error "OCaml exception raised: cannot compare";
+    ]
+  in
+  let result =
+    [
+      Rust.sort_with_comparator
+        (fun a b -> compare (String.length a) (String.length b))
+        [ "ccc"; "a"; "bb" ];
+      Rust.sort_with_comparator
+        (fun _ _ -> failwith "cannot compare")
+        [ "ccc"; "a"; "bb" ];
+    ]
+  in
+  Alcotest.(check (list (result (list string) string)))
+    "Sort with an OCaml comparator" expected result
+
+let test_join_with_callback5 () =
+  Alcotest.(check (result string string))
+    "Call a 5 argument OCaml closure from Rust" (Ok "a-b-c-d-e")
+    (Rust.join_with_callback5 (fun a b c d e -> String.concat "-" [ a; b; c; d; e ]))
+
+let test_scale_point () =
+  let point = Rust.scale_point { x = 1.5; y = -2.0 } in
+  Alcotest.(check (pair (float 0.0) (float 0.0)))
+    "Scale a float record" (3.0, -4.0) (point.x, point.y)
+
+let test_next_user_id () =
+  let (UserId id) = Rust.next_user_id (UserId 41) in
+  Alcotest.(check int) "Pass an unboxed variant through" 42 id
+
+let test_warm_reading () =
+  let reading = Rust.warm_reading { sensor = "kitchen"; celsius = 20.0 } in
+  Alcotest.(check (pair string (float 1e-9)))
+    "Convert fields with custom functions" ("kitchen", 25.0)
+    (reading.sensor, reading.celsius)
+
+let test_scale_shape () =
+  let describe = function
+    | Circle { radius } -> Printf.sprintf "Circle %g" radius
+    | Label { text; size } -> Printf.sprintf "Label %s %d" text size
+    | Square { side } -> Printf.sprintf "Square %g" side
+  in
+  let shapes =
+    [ Circle { radius = 1.5 }; Label { text = "abc"; size = 10 }; Square { side = 3.0 } ]
+  in
+  Alcotest.(check (list string))
+    "Convert boxed and inline records in variants"
+    [ "Circle 3"; "Label ABC 20"; "Square 6" ]
+    (List.map (fun shape -> describe (Rust.scale_shape shape)) shapes)
+
+let test_bump_counter () =
+  let counter = { label = "clicks"; count = 41; last_update = "" } in
+  Rust.bump_counter counter;
+  Alcotest.(check (pair int string))
+    "Update mutable fields in place" (42, "bumped clicks")
+    (counter.count, counter.last_update)
+
+let test_reverse_velocity () =
+  let velocity = { dx = 1.5; dy = -2.0 } in
+  Rust.reverse_velocity velocity;
+  Alcotest.(check (pair (float 0.0) (float 0.0)))
+    "Update mutable float record fields in place" (-1.5, 2.0)
+    (velocity.dx, velocity.dy)
+
+let test_record_accessors () =
+  let counter = { label = "clicks"; count = 7; last_update = "" } in
+  Alcotest.(check string)
+    "Read record fields without converting the record" "clicks: 7"
+    (Rust.describe_counter counter);
+  Alcotest.(check (float 0.0))
+    "Read float record fields" 3.5
+    (Rust.point_sum { x = 1.25; y = 2.25 })
+
+let deep = 100_000
+
+let test_deep_tree_roundtrip () =
+  let rec build acc n = if n = 0 then acc else build (Node (acc, n, Leaf)) (n - 1) in
+  let tree = build (Node (Leaf, 0, Leaf)) deep in
+  Alcotest.(check bool)
+    "Convert deep trees without overflowing the stack" true
+    (Rust.tree_roundtrip tree = tree)
+
+let test_increment_chain () =
+  let rec build next n = if n = 0 then next else build (Some { value = n; next }) (n - 1) in
+  let rec sum acc { value; next } =
+    match next with None -> acc + value | Some chain -> sum (acc + value) chain
+  in
+  match build None deep with
+  | None -> Alcotest.fail "empty chain"
+  | Some chain ->
+      Alcotest.(check int)
+        "Convert long chains without overflowing the stack"
+        (sum 0 chain + deep)
+        (sum 0 (Rust.increment_chain chain))
+
+let test_deep_rose_roundtrip () =
+  let leaf label = { label; children = [] } in
+  let rec build acc n =
+    if n = 0 then acc else build { label = n; children = [ leaf (-n); acc; leaf n ] } (n - 1)
+  in
+  let rose = build (leaf 0) deep in
+  Alcotest.(check bool)
+    "Convert deep trees with list children without overflowing the stack" true
+    (Rust.rose_roundtrip rose = rose)
+
+let test_make_writable () =
+  let handle : read_only handle = { id = 3 } in
+  let writable : read_write handle = Rust.make_writable handle in
+  Alcotest.(check int) "Convert records with phantom type parameters" 3 writable.id
+
+let test_bump_entry () =
+  Alcotest.(check (pair int string))
+    "Convert tuple structs as tuples" (2, "visits")
+    (Rust.bump_entry (1, "visits"))
+
+let test_next_plugin_event () =
+  let rec run acc event =
+    match Rust.next_plugin_event event with
+    | Stopped -> List.rev ("Stopped" :: acc)
+    | Progress (current, total) as event ->
+        run (Printf.sprintf "Progress %d/%d" current total :: acc) event
+    | _ -> Alcotest.fail "unexpected plugin event"
+  in
+  Alcotest.(check (list string))
+    "Convert extensible variants"
+    [ "Progress 0/2"; "Progress 1/2"; "Stopped" ]
+    (run [] (Started "ab"))
+
+let test_plugin_failure_message () =
+  Alcotest.(check string)
+    "Convert exceptions" "timeout"
+    (Rust.plugin_failure_message (Plugin_failure "timeout"))
+
 let test_byte_function () =
   let expected = 1 + 2 + 3 + 4 + 5 + 6 + 7 in
   let result = Rust.rust_rust_add_7ints 1 2 3 4 5 6 7 in
@@ -307,6 +543,23 @@ let () =
           test_case "Rust.call_ocaml_closure" `Quick test_call_ocaml_closure;
           test_case "Rust.call_ocaml_closure_and_return_exn" `Quick
             test_call_ocaml_closure_and_return_exn;
+          test_case "Rust.mapi_with_callback" `Quick test_mapi_with_callback;
+          test_case "Rust.sort_with_comparator" `Quick test_sort_with_comparator;
+          test_case "Rust.join_with_callback5" `Quick test_join_with_callback5;
+          test_case "Rust.scale_point" `Quick test_scale_point;
+          test_case "Rust.next_user_id" `Quick test_next_user_id;
+          test_case "Rust.warm_reading" `Quick test_warm_reading;
+          test_case "Rust.scale_shape" `Quick test_scale_shape;
+          test_case "Rust.bump_counter" `Quick test_bump_counter;
+          test_case "Rust.describe_counter" `Quick test_record_accessors;
+          test_case "Rust.reverse_velocity" `Quick test_reverse_velocity;
+          test_case "Rust.tree_roundtrip" `Quick test_deep_tree_roundtrip;
+          test_case "Rust.increment_chain" `Quick test_increment_chain;
+          test_case "Rust.rose_roundtrip" `Quick test_deep_rose_roundtrip;
+          test_case "Rust.make_writable" `Quick test_make_writable;
+          test_case "Rust.bump_entry" `Quick test_bump_entry;
+          test_case "Rust.next_plugin_event" `Quick test_next_plugin_event;
+          test_case "Rust.plugin_failure_message" `Quick test_plugin_failure_message;
           test_case "Rust.rust_rust_add_7ints" `Quick test_byte_function;
           test_case "Rust.rust_should_panic_with_message" `Quick test_rust_panic_with_message;
           test_case "Rust.panic_while_releasing_lock" `Quick test_panic_while_releasing_lock;
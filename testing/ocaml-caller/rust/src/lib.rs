@@ -2,11 +2,177 @@
 // SPDX-License-Identifier: MIT
 
 use ocaml_interop::{
-    alloc_error, alloc_ok, BoxRoot, FromOCaml, OCaml, OCamlBytes, OCamlException, OCamlFloat,
-    OCamlFloatArray, OCamlInt, OCamlInt32, OCamlInt64, OCamlList, OCamlRuntime, OCamlUniformArray,
-    ToOCaml,
+    alloc_error, alloc_ok, BoxRoot, FromOCaml, OCaml, OCamlAccessors, OCamlBytes, OCamlCallback,
+    OCamlDescriber, OCamlException, OCamlFloat, OCamlFloatArray, OCamlInt, OCamlInt32, OCamlInt64,
+    OCamlList, OCamlRuntime, OCamlUniformArray, ToOCaml,
 };
-use std::{thread, time};
+use std::{marker::PhantomData, thread, time};
+
+#[derive(ToOCaml, FromOCaml, OCamlAccessors)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(ToOCaml, FromOCaml)]
+#[ocaml(unboxed)]
+struct UserId(#[ocaml(as_ = "OCamlInt")] i64);
+
+#[derive(ToOCaml, FromOCaml)]
+enum Shape {
+    #[ocaml(boxed_record)]
+    Circle {
+        radius: f64,
+    },
+    #[ocaml(boxed_record)]
+    Label {
+        text: String,
+        #[ocaml(as_ = "OCamlInt")]
+        size: i64,
+    },
+    Square {
+        side: f64,
+    },
+}
+
+#[derive(ToOCaml, FromOCaml, OCamlAccessors)]
+struct Counter {
+    label: String,
+    #[ocaml(mutable, as_ = "OCamlInt")]
+    count: i64,
+    #[ocaml(mutable)]
+    last_update: String,
+}
+
+#[derive(ToOCaml, FromOCaml)]
+struct Velocity {
+    #[ocaml(mutable)]
+    dx: f64,
+    #[ocaml(mutable)]
+    dy: f64,
+}
+
+#[derive(ToOCaml, FromOCaml, OCamlDescriber)]
+enum Tree {
+    Leaf,
+    Node(
+        #[ocaml(as_ = "Tree")] Box<Tree>,
+        #[ocaml(as_ = "OCamlInt")] i64,
+        #[ocaml(as_ = "Tree")] Box<Tree>,
+    ),
+}
+
+// The default drop glue is recursive, and would overflow the stack on deep trees
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        let mut node = std::mem::replace(self, Tree::Leaf);
+        loop {
+            if let Tree::Node(left, _, right) = &mut node {
+                pending.push(std::mem::replace(&mut **left, Tree::Leaf));
+                pending.push(std::mem::replace(&mut **right, Tree::Leaf));
+            }
+            match pending.pop() {
+                Some(next) => node = next,
+                None => break,
+            }
+        }
+    }
+}
+
+#[derive(ToOCaml, FromOCaml)]
+struct Chain {
+    #[ocaml(as_ = "OCamlInt")]
+    value: i64,
+    #[ocaml(as_ = "Option<Chain>")]
+    next: Option<Box<Chain>>,
+}
+
+impl Drop for Chain {
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(mut link) = next {
+            next = link.next.take();
+        }
+    }
+}
+
+#[derive(ToOCaml, FromOCaml)]
+struct Rose {
+    #[ocaml(as_ = "OCamlInt")]
+    label: i64,
+    #[ocaml(as_ = "OCamlList<Rose>")]
+    children: Vec<Rose>,
+}
+
+impl Drop for Rose {
+    fn drop(&mut self) {
+        let mut pending = std::mem::take(&mut self.children);
+        while let Some(mut node) = pending.pop() {
+            pending.append(&mut node.children);
+        }
+    }
+}
+
+// Capabilities only exist at the type level, on both sides
+enum ReadOnly {}
+enum ReadWrite {}
+
+#[derive(ToOCaml, FromOCaml)]
+struct Handle<#[ocaml(phantom)] Cap> {
+    #[ocaml(as_ = "OCamlInt")]
+    id: i64,
+    #[ocaml(phantom)]
+    capability: PhantomData<Cap>,
+}
+
+#[derive(ToOCaml, FromOCaml)]
+#[ocaml(tuple)]
+struct Entry(#[ocaml(as_ = "OCamlInt")] i64, String);
+
+#[derive(ToOCaml, FromOCaml)]
+#[ocaml(extensible = "plugin_event")]
+enum PluginEvent {
+    Started(String),
+    Progress(
+        #[ocaml(as_ = "OCamlInt")] i64,
+        #[ocaml(as_ = "OCamlInt")] i64,
+    ),
+    Stopped,
+}
+
+#[derive(ToOCaml, FromOCaml)]
+#[ocaml(extensible = "plugin_failure", as_ = "OCamlException")]
+enum PluginFailure {
+    #[ocaml(rename = "Plugin_failure")]
+    Failed(String),
+}
+
+struct Fahrenheit(f64);
+
+// The OCaml side stores temperatures in Celsius
+mod celsius {
+    use super::Fahrenheit;
+    use ocaml_interop::{OCaml, OCamlFloat, OCamlRuntime, ToOCaml};
+
+    pub fn to_ocaml<'a>(value: &Fahrenheit, cr: &'a mut OCamlRuntime) -> OCaml<'a, OCamlFloat> {
+        ((value.0 - 32.0) / 1.8).to_ocaml(cr)
+    }
+
+    pub fn from_ocaml(value: OCaml<OCamlFloat>) -> Fahrenheit {
+        let celsius: f64 = value.to_rust();
+        Fahrenheit(celsius * 1.8 + 32.0)
+    }
+}
+
+#[derive(ToOCaml, FromOCaml, OCamlDescriber)]
+struct Reading {
+    sensor: String,
+    #[ocaml(rename = "celsius", with = "celsius", as_ = "OCamlFloat")]
+    temperature: Fahrenheit,
+    #[ocaml(skip)]
+    description: Option<String>,
+}
 
 #[derive(FromOCaml)]
 enum Movement {
@@ -202,6 +368,143 @@ pub fn rust_sleep(cr: &mut OCamlRuntime, millis: OCaml<OCamlInt>) {
     thread::sleep(time::Duration::from_millis(millis as u64));
 }
 
+#[ocaml_interop::export]
+pub fn rust_scale_point(cr: &mut OCamlRuntime, point: OCaml<Point>) -> OCaml<Point> {
+    let point: Point = point.to_rust();
+    Point {
+        x: point.x * 2.0,
+        y: point.y * 2.0,
+    }
+    .to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_next_user_id(cr: &mut OCamlRuntime, id: OCaml<UserId>) -> OCaml<UserId> {
+    let UserId(id) = id.to_rust();
+    UserId(id + 1).to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_warm_reading(cr: &mut OCamlRuntime, reading: OCaml<Reading>) -> OCaml<Reading> {
+    let reading: Reading = reading.to_rust();
+    assert!(reading.description.is_none());
+    Reading {
+        sensor: reading.sensor,
+        temperature: Fahrenheit(reading.temperature.0 + 9.0),
+        description: Some("not sent to OCaml".to_string()),
+    }
+    .to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_scale_shape(cr: &mut OCamlRuntime, shape: OCaml<Shape>) -> OCaml<Shape> {
+    let scaled = match shape.to_rust() {
+        Shape::Circle { radius } => Shape::Circle {
+            radius: radius * 2.0,
+        },
+        Shape::Label { text, size } => Shape::Label {
+            text: text.to_uppercase(),
+            size: size * 2,
+        },
+        Shape::Square { side } => Shape::Square { side: side * 2.0 },
+    };
+    scaled.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_bump_counter(cr: &mut OCamlRuntime, counter: BoxRoot<Counter>) {
+    let current: Counter = counter.to_rust(cr);
+    counter.set_count(cr, current.count + 1);
+    counter.set_last_update(cr, format!("bumped {}", current.label));
+}
+
+#[ocaml_interop::export]
+pub fn rust_reverse_velocity(cr: &mut OCamlRuntime, velocity: BoxRoot<Velocity>) {
+    let current: Velocity = velocity.to_rust(cr);
+    velocity.set_dx(cr, -current.dx);
+    velocity.set_dy(cr, -current.dy);
+}
+
+#[ocaml_interop::export]
+pub fn rust_describe_counter(cr: &mut OCamlRuntime, counter: OCaml<Counter>) -> OCaml<String> {
+    let description = format!("{}: {}", counter.label().as_str(), counter.count().to_i64());
+    description.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_point_sum(_cr: &mut OCamlRuntime, point: OCaml<Point>) -> f64 {
+    point.x() + point.y()
+}
+
+#[ocaml_interop::export]
+pub fn rust_tree_roundtrip(cr: &mut OCamlRuntime, tree: OCaml<Tree>) -> OCaml<Tree> {
+    let tree: Tree = tree.to_rust();
+    tree.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_rose_roundtrip(cr: &mut OCamlRuntime, rose: OCaml<Rose>) -> OCaml<Rose> {
+    let rose: Rose = rose.to_rust();
+    rose.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_increment_chain(cr: &mut OCamlRuntime, chain: OCaml<Chain>) -> OCaml<Chain> {
+    let mut chain: Chain = chain.to_rust();
+    let mut link = Some(&mut chain);
+    while let Some(current) = link {
+        current.value += 1;
+        link = current.next.as_deref_mut();
+    }
+    chain.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_make_writable(
+    cr: &mut OCamlRuntime,
+    handle: OCaml<Handle<ReadOnly>>,
+) -> OCaml<Handle<ReadWrite>> {
+    let handle: Handle<ReadOnly> = handle.to_rust();
+    let writable: Handle<ReadWrite> = Handle {
+        id: handle.id,
+        capability: PhantomData,
+    };
+    writable.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_bump_entry(
+    cr: &mut OCamlRuntime,
+    entry: OCaml<(OCamlInt, String)>,
+) -> OCaml<(OCamlInt, String)> {
+    let Entry(count, key) = entry.to_rust();
+    Entry(count + 1, key).to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_next_plugin_event(
+    cr: &mut OCamlRuntime,
+    event: OCaml<PluginEvent>,
+) -> OCaml<PluginEvent> {
+    let next = match event.to_rust() {
+        PluginEvent::Started(name) => PluginEvent::Progress(0, name.len() as i64),
+        PluginEvent::Progress(current, total) if current + 1 < total => {
+            PluginEvent::Progress(current + 1, total)
+        }
+        PluginEvent::Progress(..) | PluginEvent::Stopped => PluginEvent::Stopped,
+    };
+    next.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_plugin_failure_message(
+    cr: &mut OCamlRuntime,
+    failure: OCaml<OCamlException>,
+) -> OCaml<String> {
+    let PluginFailure::Failed(message) = failure.to_rust();
+    message.to_ocaml(cr)
+}
+
 #[ocaml_interop::export]
 pub fn rust_string_of_movement(cr: &mut OCamlRuntime, movement: OCaml<Movement>) -> OCaml<String> {
     let m = movement.to_rust();
@@ -265,6 +568,58 @@ pub fn rust_call_ocaml_closure_and_return_exn(
     }
 }
 
+#[ocaml_interop::export]
+pub fn rust_mapi_with_callback(
+    cr: &mut OCamlRuntime,
+    f: OCamlCallback<(OCamlInt, String), String>,
+    items: OCaml<OCamlList<String>>,
+) -> OCaml<Result<OCamlList<String>, String>> {
+    let items: Vec<String> = items.to_rust();
+    let results: Result<Vec<String>, String> = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| match f.call(cr, i as i64, item) {
+            Ok(result) => Ok(result.to_rust()),
+            Err(exception) => Err(exception.message().unwrap_or("no message".to_string())),
+        })
+        .collect();
+    results.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_sort_with_comparator(
+    cr: &mut OCamlRuntime,
+    compare: BoxRoot<fn(String, String) -> OCamlInt>,
+    items: OCaml<OCamlList<String>>,
+) -> OCaml<Result<OCamlList<String>, String>> {
+    let compare = compare.into_rust_fn::<String, String, i64>();
+    let mut items: Vec<String> = items.to_rust();
+    let mut error = None;
+    items.sort_by(|a, b| match compare(cr, a.clone(), b.clone()) {
+        Ok(ordering) => ordering.cmp(&0),
+        Err(exception) => {
+            error.get_or_insert_with(|| exception.to_string());
+            std::cmp::Ordering::Equal
+        }
+    });
+    let result: Result<Vec<String>, String> = match error {
+        Some(error) => Err(error),
+        None => Ok(items),
+    };
+    result.to_ocaml(cr)
+}
+
+#[ocaml_interop::export]
+pub fn rust_join_with_callback5(
+    cr: &mut OCamlRuntime,
+    join: BoxRoot<fn(String, String, String, String, String) -> String>,
+) -> OCaml<Result<String, String>> {
+    let join = join.into_rust_fn::<&str, &str, &str, &str, &str, String>();
+    let result: Result<String, String> =
+        join(cr, "a", "b", "c", "d", "e").map_err(|exception| exception.to_string());
+    result.to_ocaml(cr)
+}
+
 #[ocaml_interop::export(bytecode = "rust_rust_add_7ints_byte")]
 pub fn rust_rust_add_7ints(
     cr: &mut OCamlRuntime,
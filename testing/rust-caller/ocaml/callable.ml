@@ -77,6 +77,44 @@ let reverse_list_and_compact l =
  Gc.compact ();
  r
 
+let signal_mutex = Mutex.create ()
+let signal_condition = Condition.create ()
+let signalled = ref false
+
+let wait_for_signal () =
+  Mutex.lock signal_mutex;
+  while not !signalled do
+    Condition.wait signal_condition signal_mutex
+  done;
+  signalled := false;
+  Mutex.unlock signal_mutex
+
+let send_signal () =
+  Mutex.lock signal_mutex;
+  signalled := true;
+  Condition.signal signal_condition;
+  Mutex.unlock signal_mutex
+
+let lwt_on_resolve p k = Lwt.on_any p (fun v -> k (Ok v)) (fun e -> k (Error e))
+
+let lwt_wait () =
+  let p, r = Lwt.wait () in
+  let result = ref None in
+  let notification =
+    Lwt_unix.make_notification ~once:true (fun () ->
+        match !result with
+        | Some (Ok v) -> Lwt.wakeup_later r v
+        | Some (Error msg) -> Lwt.wakeup_later_exn r (Failure msg)
+        | None -> ())
+  in
+  (p, fun res -> result := Some res; Lwt_unix.send_notification notification)
+
+let lwt_main_run_int (p : int Lwt.t) = Lwt_main.run p
+
+let apply_closure2 f = f 20 22
+
+let apply_closure5 f = f "a" "b" "c" "d" "e"
+
 let double_u16_array arr =
   let open Bigarray in
   let n = Array1.dim arr in
@@ -84,8 +122,24 @@ let double_u16_array arr =
     Array1.(set arr i ((get arr i) * 2))
   done
 
+external ocaml_interop_closure_call1 : 'f -> 'a -> 'r = "ocaml_interop_closure_call1"
+external ocaml_interop_closure_call2 : 'f -> 'a -> 'b -> 'r = "ocaml_interop_closure_call2"
+external ocaml_interop_closure_call3 : 'f -> 'a -> 'b -> 'c -> 'r = "ocaml_interop_closure_call3"
+external ocaml_interop_closure_call4 : 'f -> 'a -> 'b -> 'c -> 'd -> 'r
+  = "ocaml_interop_closure_call4"
+external ocaml_interop_closure_call5 : 'f -> 'a -> 'b -> 'c -> 'd -> 'e -> 'r
+  = "ocaml_interop_closure_call5_byte" "ocaml_interop_closure_call5"
 
 let () =
+  Callback.register "ocaml_interop_closure_call1" ocaml_interop_closure_call1;
+  Callback.register "ocaml_interop_closure_call2" ocaml_interop_closure_call2;
+  Callback.register "ocaml_interop_closure_call3" ocaml_interop_closure_call3;
+  Callback.register "ocaml_interop_closure_call4" ocaml_interop_closure_call4;
+  Callback.register "ocaml_interop_closure_call5" ocaml_interop_closure_call5;
+  Callback.register "ocaml_interop_lwt_on_resolve" lwt_on_resolve;
+  Callback.register "ocaml_interop_lwt_wait" lwt_wait;
+  Callback.register "ocaml_interop_domain_spawn" Domain.spawn;
+  Callback.register "ocaml_interop_domain_join" Domain.join;
   Callback.register "increment_bytes" increment_bytes;
   Callback.register "decrement_bytes" decrement_bytes;
   Callback.register "increment_ints_list" increment_ints_list;
@@ -101,5 +155,16 @@ let () =
   Callback.register "raises_nonmessage_exception" raises_nonmessage_exception;
   Callback.register "raises_nonblock_exception" raises_nonblock_exception;
   Callback.register "gc_compact" Gc.compact;
+  Callback.register "memprof_stop" Gc.Memprof.stop;
+  Callback.register "ocaml_interop_memprof_start"
+    (fun sampling_rate callstack_size tracker ->
+      Gc.Memprof.start ~sampling_rate ~callstack_size tracker);
+  Callback.register "wait_for_signal" wait_for_signal;
+  Callback.register "send_signal" send_signal;
+  Callback.register "lwt_main_run_int" lwt_main_run_int;
+  Callback.register "sys_argv" (fun () -> Array.to_list Sys.argv);
+  Callback.register "domain_id" (fun () -> (Domain.self () :> int));
   Callback.register "reverse_list_and_compact" reverse_list_and_compact;
+  Callback.register "apply_closure2" apply_closure2;
+  Callback.register "apply_closure5" apply_closure5;
   Callback.register "double_u16_array" double_u16_array;
@@ -57,8 +57,16 @@ mod ocaml {
         pub fn raises_nonmessage_exception(unit: ());
         pub fn raises_nonblock_exception(unit: ());
         pub fn gc_compact(unit: ());
+        pub fn memprof_stop(unit: ());
+        pub fn wait_for_signal(unit: ());
+        pub fn send_signal(unit: ());
+        pub fn lwt_main_run_int(promise: LwtPromise<OCamlInt>) -> OCamlInt;
+        pub fn sys_argv(unit: ()) -> OCamlList<String>;
+        pub fn domain_id(unit: ()) -> OCamlInt;
         pub fn reverse_list_and_compact(list: OCamlList<DynBox<u16>>)
             -> OCamlList<DynBox<u16>>;
+        pub fn apply_closure2(f: fn(OCamlInt, OCamlInt) -> OCamlInt) -> OCamlInt;
+        pub fn apply_closure5(f: fn(String, String, String, String, String) -> String) -> String;
         pub fn double_u16_array(array: bigarray::Array1<u16>);
     }
 }
@@ -132,6 +140,9 @@ pub fn allocate_alot(cr: &mut OCamlRuntime) -> bool {
 
 // Tests
 
+#[cfg(test)]
+const TEST_MINOR_HEAP_SIZE: usize = 512 * 1024;
+
 #[cfg(test)]
 fn ensure_ocaml_runtime_initialized() {
     use ocaml_interop::OCamlRuntimeStartupGuard;
@@ -140,7 +151,12 @@ fn ensure_ocaml_runtime_initialized() {
     static mut OCAML_RUNTIME: Option<OCamlRuntimeStartupGuard> = None;
 
     INIT.call_once(|| {
-        let guard = OCamlRuntime::init().expect("Failed to initialize OCaml runtime");
+        let guard = ocaml_interop::OCamlRuntimeBuilder::new()
+            .argv(["rust-caller", "--test-arg"])
+            .minor_heap_size(TEST_MINOR_HEAP_SIZE)
+            .catch_startup_exceptions(true)
+            .init()
+            .expect("Failed to initialize OCaml runtime");
         unsafe {
             OCAML_RUNTIME = Some(guard);
         }
@@ -355,6 +371,586 @@ fn test_dynbox() {
     });
 }
 
+#[test]
+fn test_weak_reference() {
+    use ocaml_interop::OCamlWeak;
+
+    with_domain_lock(|cr| {
+        let value: BoxRoot<String> = "weakly held".to_string().to_boxroot(cr);
+        let weak = OCamlWeak::new(cr, &value);
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        assert!(weak.is_alive(cr));
+        let contents: Option<String> = weak.get(cr).map(|v| v.to_rust());
+        assert_eq!(contents.as_deref(), Some("weakly held"));
+
+        drop(value);
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        assert!(!weak.is_alive(cr));
+        assert!(weak.get(cr).is_none());
+    });
+}
+
+#[test]
+fn test_weak_cache() {
+    use ocaml_interop::OCamlWeakCache;
+
+    with_domain_lock(|cr| {
+        let mut cache: OCamlWeakCache<String, usize> = OCamlWeakCache::new();
+        let kept: BoxRoot<String> = "kept".to_string().to_boxroot(cr);
+        let dropped: BoxRoot<String> = "dropped".to_string().to_boxroot(cr);
+        let same_contents: BoxRoot<String> = "kept".to_string().to_boxroot(cr);
+
+        assert_eq!(cache.insert(cr, &kept, 1), None);
+        assert_eq!(cache.insert(cr, &dropped, 2), None);
+        assert_eq!(cache.insert(cr, &kept, 3), Some(1));
+        assert_eq!(cache.len(), 2);
+
+        // Keys are compared by identity, not structurally
+        assert_eq!(cache.get(cr, &kept), Some(&3));
+        assert_eq!(cache.get(cr, &same_contents), None);
+
+        drop(dropped);
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        assert_eq!(cache.purge(cr), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(cr, &kept), Some(&3));
+
+        assert_eq!(cache.remove(cr, &kept), Some(3));
+        assert!(cache.is_empty());
+    });
+}
+
+#[test]
+fn test_finalise() {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    with_domain_lock(|cr| {
+        let finalised_with = Arc::new(Mutex::new(None));
+        let finalised_last = Arc::new(AtomicBool::new(false));
+        let value: BoxRoot<String> = "finalised".to_string().to_boxroot(cr);
+
+        let finalised_with_ref = finalised_with.clone();
+        cr.finalise(&value, move |cr, v| {
+            // The value is rooted, converting it after allocating is fine
+            let _allocated: BoxRoot<String> = "allocation".to_string().to_boxroot(cr);
+            *finalised_with_ref.lock().unwrap() = Some(v.to_rust::<String>(cr));
+        });
+        let finalised_last_ref = finalised_last.clone();
+        cr.finalise_last(&value, move |_cr| {
+            finalised_last_ref.store(true, Ordering::SeqCst)
+        });
+
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        assert_eq!(finalised_with.lock().unwrap().take(), None);
+        assert!(!finalised_last.load(Ordering::SeqCst));
+
+        drop(value);
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        assert_eq!(
+            finalised_with.lock().unwrap().take().as_deref(),
+            Some("finalised")
+        );
+        assert!(finalised_last.load(Ordering::SeqCst));
+    });
+}
+
+#[test]
+fn test_gc_stats_and_control() {
+    with_domain_lock(|cr| {
+        let before = cr.gc_quick_stat();
+        assert!(allocate_alot(cr));
+        cr.gc_minor();
+        cr.gc_full_major();
+        let after = cr.gc_stat();
+        assert!(after.minor_words > before.minor_words);
+        assert!(after.minor_collections > before.minor_collections);
+        assert!(after.major_collections > before.major_collections);
+        assert!(cr.gc_counters().minor_words >= after.minor_words);
+
+        let original = cr.gc_get();
+        cr.gc_set_space_overhead(original.space_overhead + 10);
+        cr.gc_set_minor_heap_size(original.minor_heap_size * 2);
+        let updated = cr.gc_get();
+        assert_eq!(updated.space_overhead, original.space_overhead + 10);
+        assert_eq!(updated.minor_heap_size, original.minor_heap_size * 2);
+
+        cr.gc_set(&original);
+        assert_eq!(cr.gc_get(), original);
+        cr.gc_compact();
+    });
+}
+
+#[test]
+fn test_gc_alarm() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    with_domain_lock(|cr| {
+        let cycles = Arc::new(AtomicUsize::new(0));
+        let cycles_ref = cycles.clone();
+        let alarm = cr.gc_create_alarm(move |_cr| {
+            cycles_ref.fetch_add(1, Ordering::SeqCst);
+        });
+
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        let seen = cycles.load(Ordering::SeqCst);
+        assert!(seen >= 1);
+
+        alarm.delete();
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        ocaml::gc_compact(cr, OCaml::unit().as_ref());
+        assert!(cycles.load(Ordering::SeqCst) <= seen + 1);
+    });
+}
+
+#[test]
+fn test_memprof() {
+    use ocaml_interop::OCamlAllocationSample;
+    use std::sync::{Arc, Mutex};
+
+    with_domain_lock(|cr| {
+        let samples: Arc<Mutex<Vec<OCamlAllocationSample>>> = Arc::default();
+        let samples_ref = samples.clone();
+        let profile = cr
+            .memprof_start(0.01, 16, move |_cr, sample| {
+                samples_ref.lock().unwrap().push(*sample);
+            })
+            .unwrap();
+        let ints: Vec<i64> = (0..10_000).collect();
+        let _ = increment_ints_list(cr, &ints);
+        profile.stop(cr);
+
+        let samples = std::mem::take(&mut *samples.lock().unwrap());
+        assert!(!samples.is_empty());
+        assert!(samples.iter().all(|sample| sample.n_samples >= 1));
+        assert!(samples.iter().all(|sample| sample.callstack_length <= 16));
+    });
+}
+
+#[test]
+fn test_memprof_stopped_from_ocaml() {
+    with_domain_lock(|cr| {
+        let profile = cr.memprof_start(0.01, 16, |_cr, _sample| {}).unwrap();
+        ocaml::memprof_stop(cr, OCaml::unit().as_ref());
+        // Stopping the handle doesn't stop the profile again
+        profile.stop(cr);
+
+        // Another profile can be started on the same domain
+        let profile = cr.memprof_start(0.01, 16, |_cr, _sample| {}).unwrap();
+        profile.stop(cr);
+    });
+}
+
+#[test]
+fn test_memprof_already_running() {
+    with_domain_lock(|cr| {
+        let profile = cr.memprof_start(0.01, 16, |_cr, _sample| {}).unwrap();
+        let result = cr.memprof_start(0.01, 16, |_cr, _sample| {});
+        assert!(result.is_err());
+        // A dropped handle is stopped before the next profile starts
+        drop(profile);
+        let profile = cr.memprof_start(0.01, 16, |_cr, _sample| {}).unwrap();
+        profile.stop(cr);
+    });
+}
+
+#[test]
+fn test_runtime_builder_settings() {
+    with_domain_lock(|cr| {
+        let argv: Vec<String> = ocaml::sys_argv(cr, OCaml::unit().as_ref()).to_rust(cr);
+        assert_eq!(argv, vec!["rust-caller", "--test-arg"]);
+        assert_eq!(cr.gc_get().minor_heap_size, TEST_MINOR_HEAP_SIZE as i64);
+    });
+}
+
+#[test]
+fn test_domain_pool() {
+    use ocaml_interop::OCamlDomainPool;
+
+    let pool = with_domain_lock(|cr| OCamlDomainPool::new(cr, 2)).unwrap();
+    let main_domain_id: i64 =
+        with_domain_lock(|cr| ocaml::domain_id(cr, OCaml::unit().as_ref()).to_rust(cr));
+
+    let domain_ids: Vec<i64> = (0..pool.len())
+        .map(|i| {
+            pool.domain(i)
+                .unwrap()
+                .run(|cr| ocaml::domain_id(cr, OCaml::unit().as_ref()).to_rust(cr))
+        })
+        .collect();
+    assert_ne!(domain_ids[0], domain_ids[1]);
+    assert!(!domain_ids.contains(&main_domain_id));
+
+    let pool = std::sync::Arc::new(pool);
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let pool = pool.clone();
+            std::thread::spawn(move || pool.run(move |cr| twice(cr, i)))
+        })
+        .collect();
+    let results: Vec<i64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pool.run(|_cr| panic!("panic on domain"))
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(pool.run(|cr| twice(cr, 21)), 42);
+
+    std::sync::Arc::into_inner(pool).unwrap().join();
+}
+
+#[test]
+fn test_domain_lock_timeout() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    ensure_ocaml_runtime_initialized();
+
+    let (locked_sender, locked_receiver) = mpsc::channel();
+    let (release_sender, release_receiver) = mpsc::channel::<()>();
+    let holder = std::thread::spawn(move || {
+        OCamlRuntime::with_domain_lock(|_cr| {
+            locked_sender.send(()).unwrap();
+            release_receiver.recv().unwrap();
+        })
+    });
+    locked_receiver.recv().unwrap();
+
+    let stats_before = OCamlRuntime::domain_lock_stats();
+    assert!(OCamlRuntime::try_with_domain_lock(|_cr| ()).is_none());
+    let result =
+        OCamlRuntime::with_domain_lock_timeout(Duration::from_millis(50), |cr| twice(cr, 1));
+    assert_eq!(result, Err(ocaml_interop::OCamlDomainLockTimeout));
+    let stats_after = OCamlRuntime::domain_lock_stats();
+    assert!(stats_after.timeouts >= stats_before.timeouts + 2);
+    assert!(stats_after.max_wait >= Duration::from_millis(50));
+
+    release_sender.send(()).unwrap();
+    holder.join().unwrap();
+
+    let result =
+        OCamlRuntime::with_domain_lock_timeout(Duration::from_secs(10), |cr| twice(cr, 21));
+    assert_eq!(result, Ok(42));
+    assert!(OCamlRuntime::domain_lock_stats().acquisitions > stats_after.acquisitions);
+}
+
+#[test]
+fn test_try_domain_lock_race() {
+    use std::sync::{Arc, Barrier};
+    use std::time::Duration;
+
+    ensure_ocaml_runtime_initialized();
+
+    // Other tests may be using the runtime at the same time, in which case neither
+    // thread gets in, so this is retried until one does.
+    for _ in 0..100 {
+        let barrier = Arc::new(Barrier::new(2));
+        let racers: Vec<_> = (0..2)
+            .map(|_| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    OCamlRuntime::try_with_domain_lock(|_cr| {
+                        std::thread::sleep(Duration::from_millis(100))
+                    })
+                })
+            })
+            .collect();
+        let entered = racers
+            .into_iter()
+            .map(|racer| racer.join().unwrap())
+            .filter(Option::is_some)
+            .count();
+        assert!(entered <= 1, "both threads entered the runtime");
+        if entered == 1 {
+            return;
+        }
+    }
+    panic!("no thread entered the runtime");
+}
+
+#[test]
+fn test_domain_lock_released_by_ocaml() {
+    ensure_ocaml_runtime_initialized();
+
+    // `Condition.wait` releases the runtime while `wait_for_signal` blocks inside
+    // `with_domain_lock`, which must let other Rust threads in.
+    let waiter = std::thread::spawn(|| {
+        OCamlRuntime::with_domain_lock(|cr| {
+            ocaml::wait_for_signal(cr, OCaml::unit().as_ref());
+        })
+    });
+    OCamlRuntime::with_domain_lock(|cr| {
+        ocaml::send_signal(cr, OCaml::unit().as_ref());
+    });
+    waiter.join().unwrap();
+}
+
+#[cfg(test)]
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(value) => return value,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+#[test]
+fn test_lwt_main_run_with_spawned_resolver() {
+    use ocaml_interop::{LwtPromise, OCamlInt};
+
+    ensure_ocaml_runtime_initialized();
+
+    // The resolver enters the runtime from its own thread while `Lwt_main.run` is
+    // waiting for the notification inside `with_domain_lock`.
+    let result: i64 = OCamlRuntime::with_domain_lock(|cr| {
+        let (promise, resolver) =
+            LwtPromise::<OCamlInt>::from_future(cr, async { Ok::<i64, String>(42) });
+        let promise = promise.root();
+        let resolver = std::thread::spawn(move || block_on(resolver));
+        let result = ocaml::lwt_main_run_int(cr, &promise).to_rust(cr);
+        cr.releasing_runtime(|| resolver.join().unwrap());
+        result
+    });
+    assert_eq!(result, 42);
+}
+
+#[test]
+fn test_executor() {
+    use ocaml_interop::OCamlExecutor;
+
+    ensure_ocaml_runtime_initialized();
+
+    let executor = OCamlExecutor::new();
+    let tasks: Vec<_> = (0..8)
+        .map(|i| executor.spawn(move |cr| twice(cr, i)))
+        .collect();
+    let results: Vec<i64> = tasks.into_iter().map(block_on).collect();
+    assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+
+    let result = block_on(executor.clone().spawn(|cr| {
+        let s: OCaml<String> = "hello".to_ocaml(cr);
+        s.to_rust::<String>() + " world"
+    }));
+    assert_eq!(result, "hello world");
+
+    let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        block_on(executor.spawn(|_cr| panic!("panic in executor")))
+    }));
+    assert!(panicked.is_err());
+    assert_eq!(block_on(executor.spawn(|cr| twice(cr, 21))), 42);
+
+    let domain_executor = with_domain_lock(|cr| OCamlExecutor::with_domains(cr, 2)).unwrap();
+    let ids: Vec<i64> = (0..4)
+        .map(|_| {
+            domain_executor.spawn(|cr| ocaml::domain_id(cr, OCaml::unit().as_ref()).to_rust(cr))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(block_on)
+        .collect();
+    assert!(ids.iter().all(|&id| id != 0));
+}
+
+#[test]
+fn test_sendable_root() {
+    use ocaml_interop::SendableRoot;
+    use std::sync::mpsc;
+
+    ensure_ocaml_runtime_initialized();
+
+    let (sender, receiver) = mpsc::channel();
+    let producer = std::thread::spawn(move || {
+        for i in 0..10 {
+            OCamlRuntime::with_domain_lock(|cr| {
+                let value: OCaml<String> = format!("value {i}").to_ocaml(cr);
+                sender.send(SendableRoot::new(value)).unwrap();
+            });
+        }
+    });
+    producer.join().unwrap();
+
+    let mut roots: Vec<SendableRoot<String>> = receiver.iter().collect();
+    with_domain_lock(|cr| cr.gc_full_major());
+    let values: Vec<String> =
+        with_domain_lock(|cr| roots.iter().map(|r| r.get(cr).to_rust()).collect());
+    assert_eq!(
+        values,
+        (0..10).map(|i| format!("value {i}")).collect::<Vec<_>>()
+    );
+
+    // Dropped without holding the domain lock, removed when the lock is next acquired
+    let dropped = roots.split_off(5);
+    std::thread::spawn(move || drop(dropped)).join().unwrap();
+
+    let mut root = roots.pop().unwrap();
+    let updated: String = with_domain_lock(|cr| {
+        let updated: OCaml<String> = "updated".to_ocaml(cr);
+        root.keep(updated);
+        cr.gc_full_major();
+        root.get_ref(cr).to_rust(cr)
+    });
+    assert_eq!(updated, "updated");
+
+    let shared = std::sync::Arc::new(root);
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                OCamlRuntime::with_domain_lock(|cr| shared.get(cr).to_rust::<String>())
+            })
+        })
+        .collect();
+    for reader in readers {
+        assert_eq!(reader.join().unwrap(), "updated");
+    }
+}
+
+#[cfg(test)]
+static GREETING: ocaml_interop::LazyGlobalRoot<String> =
+    ocaml_interop::LazyGlobalRoot::new(|cr| "hello from a static".to_ocaml(cr));
+
+#[test]
+fn test_global_root() {
+    use ocaml_interop::GlobalRoot;
+
+    let (first, second): (String, String) = with_domain_lock(|cr| {
+        let mut root: GlobalRoot<String> = GlobalRoot::new("first".to_ocaml(cr));
+        cr.gc_full_major();
+        let first = root.to_rust(cr);
+        let second = "second".to_ocaml(cr);
+        root.keep(second);
+        cr.gc_full_major();
+        (first, root.get(cr).to_rust())
+    });
+    assert_eq!(first, "first");
+    assert_eq!(second, "second");
+
+    let greetings: Vec<String> = (0..2)
+        .map(|_| {
+            std::thread::spawn(|| {
+                OCamlRuntime::with_domain_lock(|cr| {
+                    let greeting = GREETING.get(cr).to_rust();
+                    cr.gc_full_major();
+                    greeting
+                })
+            })
+            .join()
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(greetings, vec!["hello from a static"; 2]);
+    let some_greeting: Option<String> = with_domain_lock(|cr| {
+        let greeting = GREETING.get_ref(cr);
+        ocaml::make_some(cr, greeting).to_rust(cr)
+    });
+    assert_eq!(some_greeting.as_deref(), Some("hello from a static"));
+}
+
+#[test]
+fn test_local_roots() {
+    use ocaml_interop::{ocaml_frame, LocalRoots, OCamlRef};
+
+    let (name, count): (String, i64) = with_domain_lock(|cr| {
+        ocaml_frame!(cr, (name_root, count_root), {
+            let name = name_root.keep("local".to_ocaml(cr));
+            let count = count_root.keep(7i64.to_ocaml(cr));
+            cr.gc_full_major();
+            ocaml::make_tuple(cr, name, count).to_rust(cr)
+        })
+    });
+    assert_eq!(name, "local");
+    assert_eq!(count, 7);
+
+    let values: Vec<String> = with_domain_lock(|cr| {
+        LocalRoots::<3>::scope(cr, |cr, [mut a, mut b, mut c]| {
+            let a = a.keep("a".to_ocaml(cr));
+            let nested: String = ocaml_frame!(cr, (inner), {
+                let inner: OCamlRef<String> = inner.keep("inner".to_ocaml(cr));
+                cr.gc_minor();
+                inner.to_rust(cr)
+            });
+            let b: OCamlRef<String> = b.keep(nested.to_ocaml(cr));
+            let c = c.keep(ocaml::make_some(cr, a).get(cr));
+            cr.gc_full_major();
+            vec![
+                a.to_rust(cr),
+                b.to_rust(cr),
+                c.to_rust::<Option<String>>(cr).unwrap(),
+            ]
+        })
+    });
+    assert_eq!(values, vec!["a", "inner", "a"]);
+}
+
+#[test]
+fn test_rust_closures() {
+    use ocaml_interop::OCamlInt;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let closure_calls = calls.clone();
+    let (sums, joined): (Vec<i64>, String) = with_domain_lock(|cr| {
+        let add =
+            OCaml::<fn(OCamlInt, OCamlInt) -> OCamlInt>::from_rust_closure(cr, move |cr, a, b| {
+                closure_calls.fetch_add(1, Ordering::SeqCst);
+                let sum: i64 = a.to_rust::<i64>(cr) + b.to_rust::<i64>(cr);
+                sum.to_ocaml(cr)
+            });
+        let add = BoxRoot::new(add);
+        let sums = vec![
+            ocaml::apply_closure2(cr, &add).to_rust(cr),
+            ocaml::apply_closure2(cr, &add).to_rust(cr),
+        ];
+
+        type Concat5 = fn(String, String, String, String, String) -> String;
+        let concat = OCaml::<Concat5>::from_rust_closure(cr, |cr, a, b, c, d, e| {
+            // The arguments are rooted, so they survive collections triggered from here.
+            cr.gc_full_major();
+            let parts: [String; 5] = [
+                a.to_rust(cr),
+                b.to_rust(cr),
+                c.to_rust(cr),
+                d.to_rust(cr),
+                e.to_rust(cr),
+            ];
+            parts.join("-").to_ocaml(cr)
+        });
+        let concat = BoxRoot::new(concat);
+        cr.gc_full_major();
+        (sums, ocaml::apply_closure5(cr, &concat).to_rust(cr))
+    });
+    assert_eq!(sums, vec![42, 42]);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(joined, "a-b-c-d-e");
+}
+
 #[test]
 fn test_threads() {
     let mut handles = Vec::new();